@@ -0,0 +1,385 @@
+use bevy::{prelude::*, input::mouse::MouseWheel};
+use bevy_ggrs::RollbackIdProvider;
+
+use crate::{
+    ingameui::camera::system_reset_player_camera,
+    shared::{
+        collider::MovementCollider,
+        game::{GameState, LevelMapRequested},
+        map::{
+            MapElementPosition, MapElement, Wall, Window, ZombieSpawner, PlayerSpawn,
+            render::{
+                MapDataAsset, MapDataState, MapPlayerSpawn, MapZombieSpawner, TiledMapObjectElement,
+                load_scene_system, render_scene, react_event_scene, spawn_tiled_map_objects, system_unload_map,
+                spawn_wall, spawn_window, spawn_spawner, spawn_player_spawn,
+            },
+            tiled_map::{tiled::TiledMap, texture::set_texture_filters_to_nearest},
+        },
+        player::MainCamera,
+        utils::{get_cursor_location, asset_root},
+    },
+};
+
+const EDITOR_CAMERA_SPEED: f32 = 500.;
+const EDITOR_ZOOM_SPEED: f32 = 0.1;
+const EDITOR_MIN_ELEMENT_SIZE: f32 = 5.;
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum MapEditorTool {
+    #[default]
+    Wall,
+    Window,
+    Spawner,
+    PlayerSpawn,
+}
+
+impl MapEditorTool {
+    fn default_size(&self) -> Vec2 {
+        match self {
+            MapEditorTool::Wall => Vec2::new(50., 25.),
+            MapEditorTool::Window => Vec2::new(10., 60.),
+            MapEditorTool::Spawner => Vec2::new(10., 10.),
+            MapEditorTool::PlayerSpawn => Vec2::new(50., 50.),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct MapEditorState {
+    pub tool: MapEditorTool,
+    pub selected: Option<Entity>,
+    // offset between the cursor and the selected element while dragging it
+    pub dragging: Option<Vec2>,
+    pub status: String,
+}
+
+#[derive(Component, Default)]
+pub struct MapEditorUI {}
+
+#[derive(Component, Default)]
+pub struct MapEditorSelection {}
+
+// Tile grid of the map where the elements are snap , in world unit
+fn map_grid(
+    tiled_maps: &Assets<TiledMap>,
+    q_map: &Query<(&Transform, &Handle<TiledMap>), Without<MainCamera>>,
+) -> Option<(Vec2, Vec2)> {
+    for (transform, handle) in q_map.iter() {
+        if let Some(tiled_map) = tiled_maps.get(handle) {
+            let tile_size = Vec2::new(tiled_map.map.tile_width as f32, tiled_map.map.tile_height as f32);
+            let grid = match tiled_map.map.orientation {
+                tiled::Orientation::Orthogonal => tile_size,
+                _ => tile_size / 2.,
+            };
+            return Some((transform.translation.truncate(), grid));
+        }
+    }
+    None
+}
+
+fn snap(position: Vec2, grid: Option<(Vec2, Vec2)>) -> Vec2 {
+    match grid {
+        Some((origin, size)) => origin + ((position - origin) / size).round() * size,
+        None => position.round(),
+    }
+}
+
+fn contains(position: &MapElementPosition, point: Vec2) -> bool {
+    let distance = (position.position - point).abs();
+    distance.x <= position.size.x / 2. && distance.y <= position.size.y / 2.
+}
+
+pub fn setup_map_editor(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(MapEditorState::default());
+
+    commands.spawn().insert(MapEditorUI{}).insert_bundle(TextBundle {
+        text: Text::with_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 18.0,
+                color: Color::WHITE,
+            },
+            default(),
+        ),
+        ..default()
+    });
+
+    commands.spawn().insert(MapEditorSelection{}).insert_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgba(1., 0.9, 0.2, 0.4),
+            ..default()
+        },
+        visibility: Visibility { is_visible: false },
+        ..default()
+    });
+}
+
+// The player spawns have no sprite in game , draw them in the editor
+pub fn system_editor_show_player_spawns(
+    mut commands: Commands,
+    q_spawn: Query<(Entity, &MapElementPosition), (With<PlayerSpawn>, Without<Sprite>)>,
+) {
+    for (entity, position) in q_spawn.iter() {
+        commands.entity(entity).insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgb(0.2, 0.7, 0.3),
+                custom_size: Some(position.size),
+                ..default()
+            },
+            transform: Transform::from_translation(position.position.extend(10.)),
+            ..default()
+        });
+    }
+}
+
+pub fn system_editor_camera(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut ev_wheel: EventReader<MouseWheel>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    // ctrl is use for the shortcuts of the editor
+    if keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl) {
+        return;
+    }
+
+    let mut direction = Vec2::ZERO;
+    if keys.pressed(KeyCode::W) { direction.y += 1.; }
+    if keys.pressed(KeyCode::S) { direction.y -= 1.; }
+    if keys.pressed(KeyCode::A) { direction.x -= 1.; }
+    if keys.pressed(KeyCode::D) { direction.x += 1.; }
+
+    let zoom: f32 = ev_wheel.iter().map(|ev| ev.y).sum();
+
+    for (mut transform, mut projection) in q_camera.iter_mut() {
+        let movement = direction.normalize_or_zero() * EDITOR_CAMERA_SPEED * projection.scale * time.delta_seconds();
+        transform.translation += movement.extend(0.);
+        projection.scale = (projection.scale * (1. - zoom * EDITOR_ZOOM_SPEED)).clamp(0.2, 5.);
+    }
+}
+
+pub fn system_editor_input(
+    mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    wnds: Res<Windows>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+
+    mut state: ResMut<MapEditorState>,
+    mut app_state: ResMut<State<GameState>>,
+
+    tiled_maps: Res<Assets<TiledMap>>,
+    q_map: Query<(&Transform, &Handle<TiledMap>), Without<MainCamera>>,
+
+    mut q_element: Query<
+        (Entity, &mut MapElementPosition, &mut Transform, Option<&mut Sprite>, Option<&mut MovementCollider>),
+        (With<MapElement>, Without<TiledMapObjectElement>, Without<MainCamera>, Without<Handle<TiledMap>>),
+    >,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        app_state.set(GameState::Menu).unwrap();
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Key1) { state.tool = MapEditorTool::Wall; }
+    if keys.just_pressed(KeyCode::Key2) { state.tool = MapEditorTool::Window; }
+    if keys.just_pressed(KeyCode::Key3) { state.tool = MapEditorTool::Spawner; }
+    if keys.just_pressed(KeyCode::Key4) { state.tool = MapEditorTool::PlayerSpawn; }
+
+    let grid = map_grid(&tiled_maps, &q_map);
+    let cursor = get_cursor_location(&wnds, &q_camera);
+
+    if buttons.just_pressed(MouseButton::Left) {
+        // select the smallest element under the cursor , so a window in a wall can be selected
+        let mut selected: Option<(Entity, f32, Vec2)> = None;
+        for (entity, position, ..) in q_element.iter() {
+            if contains(&position, cursor) {
+                let area = position.size.x * position.size.y;
+                if selected.is_none() || area < selected.unwrap().1 {
+                    selected = Some((entity, area, position.position - cursor));
+                }
+            }
+        }
+
+        if let Some((entity, _, offset)) = selected {
+            state.selected = Some(entity);
+            state.dragging = Some(offset);
+        } else {
+            let position = MapElementPosition { position: snap(cursor, grid), size: state.tool.default_size(), rotation: 1 };
+            let entity = match state.tool {
+                MapEditorTool::Wall => spawn_wall(&mut commands, position),
+                MapEditorTool::Window => spawn_window(&mut commands, position),
+                MapEditorTool::Spawner => spawn_spawner(&mut commands, &mut rip, position, ZombieSpawner::default()),
+                MapEditorTool::PlayerSpawn => spawn_player_spawn(&mut commands, position, PlayerSpawn::default()),
+            };
+            state.selected = Some(entity);
+            state.dragging = None;
+            state.status = format!("Added a {:?}", state.tool);
+        }
+    }
+
+    if buttons.just_released(MouseButton::Left) {
+        state.dragging = None;
+    }
+
+    let selected = match state.selected {
+        Some(entity) => entity,
+        None => return,
+    };
+
+    if keys.just_pressed(KeyCode::Delete) || keys.just_pressed(KeyCode::Back) {
+        commands.entity(selected).despawn_recursive();
+        state.selected = None;
+        state.dragging = None;
+        state.status = "Element deleted".to_string();
+        return;
+    }
+
+    if let Ok((_, mut position, mut transform, sprite, collider)) = q_element.get_mut(selected) {
+        if let Some(offset) = state.dragging {
+            if buttons.pressed(MouseButton::Left) {
+                position.position = snap(cursor + offset, grid);
+            }
+        }
+
+        let step = grid.map(|(_, size)| size).unwrap_or(Vec2::splat(EDITOR_MIN_ELEMENT_SIZE));
+        if keys.just_pressed(KeyCode::Right) { position.size.x += step.x; }
+        if keys.just_pressed(KeyCode::Left) { position.size.x -= step.x; }
+        if keys.just_pressed(KeyCode::Up) { position.size.y += step.y; }
+        if keys.just_pressed(KeyCode::Down) { position.size.y -= step.y; }
+        position.size = position.size.max(Vec2::splat(EDITOR_MIN_ELEMENT_SIZE));
+
+        transform.translation.x = position.position.x;
+        transform.translation.y = position.position.y;
+        if let Some(mut sprite) = sprite {
+            sprite.custom_size = Some(position.size);
+        }
+        if let Some(mut collider) = collider {
+            collider.size = position.size;
+        }
+    }
+}
+
+pub fn system_editor_save(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<MapEditorState>,
+    map_state: Res<MapDataState>,
+    map_assets: Res<Assets<MapDataAsset>>,
+    level_requested: Res<LevelMapRequested>,
+
+    q_wall: Query<&MapElementPosition, (With<Wall>, Without<TiledMapObjectElement>)>,
+    q_window: Query<&MapElementPosition, (With<Window>, Without<TiledMapObjectElement>)>,
+    q_spawner: Query<(&MapElementPosition, &ZombieSpawner), Without<TiledMapObjectElement>>,
+    q_player_spawn: Query<(&MapElementPosition, &PlayerSpawn), Without<TiledMapObjectElement>>,
+) {
+    let ctrl = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    if !(ctrl && keys.just_pressed(KeyCode::S)) {
+        return;
+    }
+
+    let mut map_data = match map_assets.get(&map_state.handle) {
+        Some(map_data) => map_data.clone(),
+        None => return,
+    };
+
+    map_data.walls = q_wall.iter().cloned().collect();
+    map_data.windows = q_window.iter().cloned().collect();
+    map_data.spawners = q_spawner.iter().map(|(position, spawner)| MapZombieSpawner {
+        position: position.position,
+        size: position.size,
+        rotation: position.rotation,
+        weight: spawner.weight,
+        zone: spawner.zone.clone(),
+        cooldown: spawner.cooldown,
+    }).collect();
+    map_data.player_spawns = q_player_spawn.iter().map(|(position, spawn)| MapPlayerSpawn {
+        position: position.position,
+        zone: spawn.zone.clone(),
+        team: spawn.team,
+    }).collect();
+
+    let config = ron::ser::PrettyConfig::new().struct_names(true);
+    let path = asset_root().join(level_requested.map.as_str());
+    state.status = match ron::ser::to_string_pretty(&map_data, config) {
+        Ok(data) => match std::fs::write(&path, data) {
+            Ok(_) => format!("Saved {}", level_requested.map),
+            Err(err) => format!("Can't write {:?} : {}", path, err),
+        },
+        Err(err) => format!("Can't serialize the map : {}", err),
+    };
+    println!("{}", state.status);
+}
+
+pub fn system_editor_ui(
+    state: Res<MapEditorState>,
+    mut q_text: Query<&mut Text, With<MapEditorUI>>,
+    q_element: Query<&MapElementPosition, Without<MapEditorSelection>>,
+    mut q_selection: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<MapEditorSelection>>,
+) {
+    let selected = state.selected.and_then(|x| q_element.get(x).ok());
+
+    for mut text in q_text.iter_mut() {
+        let selected_text = match selected {
+            Some(position) => format!("{} {}", position.position, position.size),
+            None => "none".to_string(),
+        };
+        text.sections[0].value = format!(
+            "Tool: {:?} (1 wall , 2 window , 3 spawner , 4 player spawn)\nSelected: {}\nClick place or select , drag move , arrows resize , delete remove\nCtrl+S save , Esc quit\n{}",
+            state.tool, selected_text, state.status,
+        );
+    }
+
+    for (mut transform, mut sprite, mut visibility) in q_selection.iter_mut() {
+        visibility.is_visible = selected.is_some();
+        if let Some(position) = selected {
+            transform.translation = position.position.extend(20.);
+            sprite.custom_size = Some(position.size + Vec2::splat(4.));
+        }
+    }
+}
+
+pub fn system_clear_map_editor(
+    mut commands: Commands,
+    q_editor: Query<Entity, Or<(With<MapEditorUI>, With<MapEditorSelection>)>>,
+) {
+    for entity in q_editor.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MapEditorState>();
+}
+
+pub struct MapEditorPlugin {}
+
+impl Plugin for MapEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_system_set(
+            SystemSet::on_enter(GameState::MapEditor)
+                .with_system(load_scene_system)
+                .with_system(setup_map_editor)
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::MapEditor)
+                .with_system(react_event_scene)
+                .with_system(render_scene)
+                .with_system(spawn_tiled_map_objects)
+                .with_system(set_texture_filters_to_nearest)
+                .with_system(system_editor_show_player_spawns)
+                .with_system(system_editor_camera)
+                .with_system(system_editor_input)
+                .with_system(system_editor_save)
+                .with_system(system_editor_ui)
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::MapEditor)
+                .with_system(system_unload_map)
+                .with_system(system_clear_map_editor)
+                .with_system(system_reset_player_camera)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::shared::{player::{MainCamera, Player, CameraShakeEvent}, character::Death, health::Health, map::tiled_map::tiled::TiledMap, weapons::{weapons::{ActiveWeapon, AmmunitionState, Weapon}, equipment::Explosion}};
+
+const PLAYER_HIT_CAMERA_SHAKE: f32 = 0.5;
+const EXPLOSION_CAMERA_SHAKE: f32 = 0.4;
+
+// Configuration of the camera shared by the local players
+pub struct PlayerCameraConfig {
+    // space to keep between the players and the border of the screen
+    pub margin: f32,
+    // zoom when the players are close to each other
+    pub min_scale: f32,
+    // maximum zoom out when the players are far from each other
+    pub max_scale: f32,
+    // how fast the camera catch up with the players , 0 to follow instantly
+    pub smoothing: f32,
+    // distance the players can move from the center before the camera follow
+    pub dead_zone: Vec2,
+    // offset of the camera when the trauma is at the maximum
+    pub max_shake_offset: f32,
+    // trauma removed each second
+    pub trauma_decay: f32,
+}
+
+impl Default for PlayerCameraConfig {
+    fn default() -> Self {
+        PlayerCameraConfig {
+            margin: 100.,
+            min_scale: 1.,
+            max_scale: 3.,
+            smoothing: 5.,
+            dead_zone: Vec2::new(50., 30.),
+            max_shake_offset: 15.,
+            trauma_decay: 1.5,
+        }
+    }
+}
+
+// Position of the camera before the shake is applied
+#[derive(Default)]
+pub struct PlayerCameraState {
+    pub position: Option<Vec2>,
+    pub trauma: f32,
+}
+
+fn follow_with_dead_zone(position: f32, target: f32, dead_zone: f32) -> f32 {
+    if target > position + dead_zone {
+        target - dead_zone
+    } else if target < position - dead_zone {
+        target + dead_zone
+    } else {
+        position
+    }
+}
+
+fn clamp_to_bounds(position: f32, half_view: f32, min: f32, max: f32) -> f32 {
+    if max - min <= half_view * 2. {
+        (min + max) / 2.
+    } else {
+        position.clamp(min + half_view, max - half_view)
+    }
+}
+
+// Move and zoom the main camera to keep every local player in the view ,
+// without leaving the bounds of the map. The split-screen mode is a follow-up (see README)
+pub fn system_camera_frame_local_players(
+    windows: Res<Windows>,
+    config: Res<PlayerCameraConfig>,
+    mut state: ResMut<PlayerCameraState>,
+    mut ev_shake: EventReader<CameraShakeEvent>,
+    time: Res<Time>,
+
+    tiled_maps: Res<Assets<TiledMap>>,
+    q_map: Query<(&Transform, &Handle<TiledMap>), (Without<MainCamera>, Without<Player>)>,
+
+    q_player: Query<(&Transform, &Player, Option<&Death>), Without<MainCamera>>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    for ev in ev_shake.iter() {
+        state.trauma = (state.trauma + ev.trauma).min(1.);
+    }
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    // only frame the players still alive , if every one is dead frame them all
+    let alive_players = q_player.iter().filter(|(_, p, death)| p.is_local && death.is_none()).count();
+    let positions: Vec<Vec2> = q_player.iter()
+        .filter(|(_, p, death)| p.is_local && (alive_players == 0 || death.is_none()))
+        .map(|(t, _, _)| t.translation.truncate())
+        .collect();
+
+    if positions.len() == 0 {
+        return;
+    }
+
+    let min = positions.iter().fold(positions[0], |acc, p| acc.min(*p));
+    let max = positions.iter().fold(positions[0], |acc, p| acc.max(*p));
+
+    let center = (min + max) / 2.;
+    let required_size = (max - min) + Vec2::splat(config.margin * 2.);
+    let scale = (required_size.x / window.width()).max(required_size.y / window.height())
+        .clamp(config.min_scale, config.max_scale);
+
+    let previous_position = state.position.unwrap_or(center);
+    let target = Vec2::new(
+        follow_with_dead_zone(previous_position.x, center.x, config.dead_zone.x),
+        follow_with_dead_zone(previous_position.y, center.y, config.dead_zone.y),
+    );
+
+    let mut position = if config.smoothing > 0. {
+        previous_position + (target - previous_position) * (1. - (-config.smoothing * time.delta_seconds()).exp())
+    } else {
+        target
+    };
+
+    let half_view = Vec2::new(window.width(), window.height()) * scale / 2.;
+    for (map_transform, handle) in q_map.iter() {
+        if let Some(tiled_map) = tiled_maps.get(handle) {
+            let (map_min, map_max) = tiled_map.world_bounds(map_transform.translation.truncate());
+            position.x = clamp_to_bounds(position.x, half_view.x, map_min.x, map_max.x);
+            position.y = clamp_to_bounds(position.y, half_view.y, map_min.y, map_max.y);
+        }
+    }
+
+    state.position = Some(position);
+
+    // the shake is proportional to the square of the trauma so small hits stay subtle
+    let shake = config.max_shake_offset * state.trauma * state.trauma;
+    let seconds = time.seconds_since_startup() as f32;
+    let shake_offset = Vec2::new((seconds * 37.1).sin(), (seconds * 41.3 + 1.7).sin()) * shake;
+    state.trauma = (state.trauma - config.trauma_decay * time.delta_seconds()).max(0.);
+
+    for (mut transform, mut projection) in q_camera.iter_mut() {
+        transform.translation.x = position.x + shake_offset.x;
+        transform.translation.y = position.y + shake_offset.y;
+        if projection.scale != scale {
+            projection.scale = scale;
+        }
+    }
+}
+
+// Shake the camera when a local player is hit , fire or is close to an explosion , the state
+// shown is compared with the last update so a frame simulated again by a rollback don't shake twice
+pub fn system_camera_shake(
+    mut ev_shake: EventWriter<CameraShakeEvent>,
+    mut last_health: Local<HashMap<usize, f32>>,
+    mut last_magasin: Local<HashMap<Entity, i32>>,
+    mut last_explosions: Local<HashSet<u32>>,
+
+    q_player: Query<(&Player, &Health, &Transform)>,
+    q_weapon: Query<(Entity, &Parent, &Weapon, &AmmunitionState), With<ActiveWeapon>>,
+    q_explosion: Query<(&Explosion, &Transform)>,
+) {
+    for (player, health, _) in q_player.iter() {
+        let previous = last_health.insert(player.handle, health.current_health);
+        if player.is_local && previous.map(|x| health.current_health < x).unwrap_or(false) {
+            ev_shake.send(CameraShakeEvent { trauma: PLAYER_HIT_CAMERA_SHAKE });
+        }
+    }
+
+    for (entity, parent, weapon, ammunition_state) in q_weapon.iter() {
+        let previous = last_magasin.insert(entity, ammunition_state.mag_remaining);
+        let is_local = q_player.get(parent.0).map(|(p, _, _)| p.is_local).unwrap_or(false);
+        if is_local && weapon.camera_shake > 0. && previous.map(|x| ammunition_state.mag_remaining < x).unwrap_or(false) {
+            ev_shake.send(CameraShakeEvent { trauma: weapon.camera_shake });
+        }
+    }
+
+    // an explosion simulated again is spawned again , it's known by the grenade that exploded
+    let mut explosions = HashSet::new();
+    for (explosion, transform) in q_explosion.iter() {
+        explosions.insert(explosion.grenade);
+        if last_explosions.contains(&explosion.grenade) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let close = q_player.iter().any(|(player, _, player_transform)| {
+            player.is_local && player_transform.translation.truncate().distance(position) < explosion.radius
+        });
+        if close {
+            ev_shake.send(CameraShakeEvent { trauma: EXPLOSION_CAMERA_SHAKE });
+        }
+    }
+    *last_explosions = explosions;
+}
+
+pub fn system_reset_player_camera(
+    mut state: ResMut<PlayerCameraState>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    state.position = None;
+    state.trauma = 0.;
+    for (mut transform, mut projection) in q_camera.iter_mut() {
+        transform.translation.x = 0.;
+        transform.translation.y = 0.;
+        projection.scale = 1.;
+    }
+}
@@ -2,9 +2,9 @@
 
 use bevy::prelude::*;
 use crate::shared::{
-    game::ZombieGame,
-    weapons::weapons::{AmmunitionState, Weapon, WeaponState, WeaponCurrentAction, ActiveWeapon},
-    zombies::zombie::Zombie, player::Player,
+    game::{ZombieGame, ZombieGameState, GameSpeed, RoundStartEvent, ZombieGameConfig},
+    weapons::{weapons::{AmmunitionState, Weapon, WeaponState, WeaponCurrentAction, ActiveWeapon}, equipment::LethalEquipment},
+    zombies::zombie::{Zombie, ZombieKind}, player::Player, health::Health,
 };
 
 #[derive(Component)]
@@ -18,6 +18,20 @@ pub struct PlayerUI {
 #[derive(Component)]
 pub struct RoundText;
 
+// Big text in the middle of the screen for the countdowns and the start of the rounds
+#[derive(Component)]
+pub struct RoundAnnouncementText;
+
+// seconds the number of the round is display when it start
+const ROUND_ANNOUNCEMENT_DURATION: f32 = 2.;
+
+// Health bar at the top of the screen , only display while a boss is alive
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthBarFill;
+
 #[derive(Component)]
 pub struct WeaponText {}
 
@@ -54,6 +68,129 @@ pub fn setup_ingame_ui(
         },
         ..default()
     }).insert(RoundText{});
+
+    commands.spawn().insert(InGameUI{}).insert_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: UiColor(Color::NONE),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 50.0,
+                    color: Color::rgb(0.8, 0.1, 0.1),
+                },
+                TextAlignment {
+                    horizontal: HorizontalAlign::Center,
+                    ..default()
+                },
+            ),
+            ..default()
+        }).insert(RoundAnnouncementText{});
+    });
+
+    commands.spawn().insert(InGameUI{}).insert_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::FlexEnd,
+            ..default()
+        },
+        color: UiColor(Color::NONE),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(400.), Val::Px(20.)),
+                margin: Rect::all(Val::Px(30.)),
+                display: Display::None,
+                ..default()
+            },
+            color: UiColor(Color::rgba(0.1, 0.1, 0.1, 0.8)),
+            ..default()
+        }).insert(BossHealthBar{}).with_children(|parent| {
+            parent.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    ..default()
+                },
+                color: UiColor(Color::rgb(0.7, 0.1, 0.1)),
+                ..default()
+            }).insert(BossHealthBarFill{});
+        });
+    });
+}
+
+pub fn system_boss_health_ui(
+    query_zombies: Query<(&Zombie, &Health)>,
+    mut query_bar: Query<&mut Style, (With<BossHealthBar>, Without<BossHealthBarFill>)>,
+    mut query_fill: Query<&mut Style, (With<BossHealthBarFill>, Without<BossHealthBar>)>,
+) {
+    let (current, max) = query_zombies.iter()
+        .filter(|(zombie, _)| zombie.kind == ZombieKind::Boss)
+        .fold((0., 0.), |acc, (_, health)| (acc.0 + health.current_health.max(0.), acc.1 + health.max_health));
+
+    let display = if max > 0. { Display::Flex } else { Display::None };
+    for mut style in query_bar.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    if max > 0. {
+        for mut style in query_fill.iter_mut() {
+            style.size.width = Val::Percent(current / max * 100.);
+        }
+    }
+}
+
+pub fn system_round_announcement_ui(
+    q_zombie_game: Query<&ZombieGame>,
+    game_speed: Res<GameSpeed>,
+    zombie_game_config: Res<ZombieGameConfig>,
+    time: Res<Time>,
+    mut ev_round_start: EventReader<RoundStartEvent>,
+    mut announcement_remaining: Local<f32>,
+    mut query_announcement: Query<&mut Text, With<RoundAnnouncementText>>,
+) {
+    if ev_round_start.iter().count() > 0 {
+        *announcement_remaining = ROUND_ANNOUNCEMENT_DURATION;
+    }
+    *announcement_remaining = (*announcement_remaining - time.delta_seconds()).max(0.);
+
+    let zombie_game = match q_zombie_game.get_single() {
+        Ok(zombie_game) => zombie_game,
+        Err(_) => return,
+    };
+
+    let special = zombie_game.special_round.and_then(|x| zombie_game_config.special_rounds.get(x));
+    let special_name = match special.map(|x| x.kind) {
+        Some(ZombieKind::Hellhound) => "\nHellhounds !",
+        Some(ZombieKind::Boss) => "\nBoss !",
+        _ => "",
+    };
+    let reward = if special.is_some() { "\nMax ammo !" } else { "" };
+
+    let value = match zombie_game.state {
+        ZombieGameState::Starting => format!("Round {}{}\n{}", zombie_game.round, special_name, zombie_game.countdown_seconds(&game_speed).ceil()),
+        ZombieGameState::RoundInterlude => format!("Round {} survived{}\nNext round in {}", zombie_game.round, reward, zombie_game.countdown_seconds(&game_speed).ceil()),
+        ZombieGameState::Round if *announcement_remaining > 0. => format!("Round {}{}", zombie_game.round, special_name),
+        _ => "".to_string(),
+    };
+
+    for mut text in query_announcement.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
 }
 
 pub fn system_ingame_ui(
@@ -75,8 +212,9 @@ pub fn system_ingame_ui(
 	let mut text = query_round.single_mut();
     text.sections[0].value = format!("Round: {} \n", zombie_game.round);
     text.sections[1].value = format!(
-        "Remaining: {} ",
-        zombie_game.current_round.zombie_remaining + nbr_zombie
+        "Remaining: {} \nPoints: {} ",
+        zombie_game.current_round.zombie_remaining + nbr_zombie,
+        zombie_game.points
     );
 
 
@@ -127,7 +265,7 @@ pub fn system_ingame_ui(
 }
 
 pub fn system_weapon_ui(
-    query_player: Query<&Children, With<Player>>,
+    query_player: Query<(&Children, Option<&LethalEquipment>), With<Player>>,
 	query_player_weapon: Query<(&AmmunitionState, &Weapon, &WeaponState), With<ActiveWeapon>>,
     query_player_ui: Query<(Entity, &Children, &PlayerUI)>,
 
@@ -137,14 +275,22 @@ pub fn system_weapon_ui(
     asset_server: Res<AssetServer>,
 ) {
     for (_, childrens_ui, player_ui) in query_player_ui.iter() {
-            if let Ok(childrens_player) = query_player.get(player_ui.player) {
+            if let Ok((childrens_player, equipment)) = query_player.get(player_ui.player) {
+                let equipment = equipment.map(|e| format!("\n{} x{}", e.name, e.remaining)).unwrap_or_default();
 
                 for children in childrens_player.iter() {
                     if let Ok((ammo_state, weapon, weapon_state)) = query_player_weapon.get(*children) {
 
                         for children in childrens_ui.iter() {
                             if let Ok(mut text) = query_ammo_text.get_mut(*children) {
-							    text.sections[0].value = format!("{}\n-\n{}", ammo_state.mag_remaining, ammo_state.remaining_ammunition);
+                                // the reserve is full , picking up ammunition is useless
+                                if ammo_state.is_full(&weapon.ammunition) {
+                                    text.sections[0].value = format!("{}\n{}\n-\n{} max{}", weapon.name, ammo_state.mag_remaining, ammo_state.remaining_ammunition, equipment);
+                                    text.sections[0].style.color = Color::rgb(1., 0.8, 0.2);
+                                } else {
+                                    text.sections[0].value = format!("{}\n{}\n-\n{}{}", weapon.name, ammo_state.mag_remaining, ammo_state.remaining_ammunition, equipment);
+                                    text.sections[0].style.color = Color::WHITE;
+                                }
                             }
                             if let Ok(mut weapon_image) = query_weapon_image.get_mut(*children) {
                                 // TODO: added a event of weapon change to trigger this instead of every frame lol
@@ -1,2 +1,15 @@
 pub mod ingameui;
-pub mod player;
\ No newline at end of file
+pub mod player;
+pub mod spectator;
+pub mod camera;
+
+use bevy::prelude::*;
+
+// Ordering of the in game systems running outside the rollback schedule
+#[derive(SystemLabel, Debug, Clone, Hash, Eq, PartialEq)]
+pub enum InGameSystemLabel {
+    // Camera following the local players , the camera effects are added before
+    CameraFrame,
+    // Announcement of the rounds , the round events are sent before
+    RoundAnnouncement,
+}
@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+
+use crate::{p2p::spectator::SpectatorClient, shared::player::{MainCamera, Player}};
+
+const SPECTATOR_CAMERA_SPEED: f32 = 400.;
+
+// State of the camera when watching a match as a spectator
+#[derive(Default)]
+pub struct SpectatorCamera {
+    // handle of the player followed by the camera , none when moving freely
+    pub following: Option<usize>,
+}
+
+// Free camera for the spectator , WASD move the camera and Tab
+// cycle between the players to follow
+pub fn system_spectator_camera(
+    spectator: Option<Res<SpectatorClient>>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+
+    q_player: Query<(&Transform, &Player), Without<MainCamera>>,
+    mut q_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    if spectator.is_none() {
+        return;
+    }
+
+    let mut camera_transform = match q_camera.get_single_mut() {
+        Ok(t) => t,
+        Err(_) => return,
+    };
+
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        let mut handles: Vec<usize> = q_player.iter().map(|(_, p)| p.handle).collect();
+        handles.sort();
+        spectator_camera.following = match spectator_camera.following {
+            Some(current) => handles.iter().find(|&&h| h > current).or(handles.first()).cloned(),
+            None => handles.first().cloned(),
+        };
+    }
+
+    let mut movement = Vec2::default();
+    if keyboard_input.pressed(KeyCode::W) {
+        movement.y += 1.;
+    }
+    if keyboard_input.pressed(KeyCode::S) {
+        movement.y -= 1.;
+    }
+    if keyboard_input.pressed(KeyCode::A) {
+        movement.x -= 1.;
+    }
+    if keyboard_input.pressed(KeyCode::D) {
+        movement.x += 1.;
+    }
+
+    if movement != Vec2::default() {
+        spectator_camera.following = None;
+        let delta = movement.normalize() * SPECTATOR_CAMERA_SPEED * time.delta_seconds();
+        camera_transform.translation += delta.extend(0.);
+        return;
+    }
+
+    if let Some(handle) = spectator_camera.following {
+        if let Some((player_transform, _)) = q_player.iter().find(|(_, p)| p.handle == handle) {
+            camera_transform.translation.x = player_transform.translation.x;
+            camera_transform.translation.y = player_transform.translation.y;
+        }
+    }
+}
+
+pub fn system_reset_spectator_camera(
+    mut spectator_camera: ResMut<SpectatorCamera>,
+) {
+    spectator_camera.following = None;
+}
@@ -8,24 +8,26 @@ mod character_animation;
 mod menu;
 
 mod p2p;
+#[cfg(all(feature = "editor", not(target_arch = "wasm32")))]
+mod editor;
 
 use bevy::{
     core::FixedTimestep, prelude::*, window::WindowDescriptor, ecs::schedule::ShouldRun
 };
 
-use bevy_ggrs::{SessionType, GGRSPlugin};
+use bevy_ggrs::SessionType;
 use bytemuck::{Pod, Zeroable};
 use ggrs::{SessionBuilder, Config, P2PSession};
 use shared::{
     game::{
-        react_level_data, setup_zombie_game, system_zombie_game,
-        GameState, ZombieGamePlugin, LevelMapRequested, system_unload_zombie_game, system_end_game, increase_frame_system, ZombieGame, GameSpeed,
+        react_level_data, setup_zombie_game,
+        GameState, ZombieGamePlugin, LevelMapRequested, DEFAULT_MAP, DEFAULT_LEVEL, system_unload_zombie_game, system_round_events, GameSpeed,
     },
-    zombies::zombie::{system_zombie_handle, Zombie, BotDestination, system_move_zombie},
-    player::{input::{apply_input_players, FrameCount, input, BoxInput, AvailableGameController, system_gamepad_event, GGRSConfig, update_velocity_player, move_players}, interaction::system_interaction_player, system_unload_players, system_health_player, Player
-    }, weapons::{weapons::{handle_weapon_input, Weapon, AmmunitionState, Projectile}, ammunition::{apply_velocity, movement_projectile}}, map::{render::system_unload_map, ZombieSpawner}, character::{Velocity, LookingAt, Death, CharacterMovementState}, health::{Health, HealthRegeneration}, collider::ProjectileCollider,
+    player::{input::{FrameCount, input, AvailableGameController, system_gamepad_event, GGRSConfig}, system_unload_players
+    }, map::render::system_unload_map,
 };
 use shared::map::MapPlugin;
+use shared::weapons::loader::WeaponAssetErrorUiPlugin;
 use crate::{
     plugins::{
         frame_cnt::FPSPlugin,
@@ -33,21 +35,24 @@ use crate::{
     },
     character_animation::CharacterAnimationPlugin,
     menu::{
-        homemenu::{HomeMenuPlugin, clear_home_menu, system_button_handle}, onlinemenu::OnlineMenuPlugin, 
+        homemenu::{HomeMenuPlugin, clear_home_menu, system_button_handle}, onlinemenu::OnlineMenuPlugin, levelselect::LevelSelectPlugin,
     },
     ingameui::{
-        ingameui::{system_clear_ingame_ui, system_weapon_ui, system_ingame_ui, setup_ingame_ui},
-        player::{setup_player_camera, system_player_added}
-    }, p2p::{config::P2PSystemLabel, checksum::{checksum_zombie, checksum_zombiegame}, online::system_cleanup_network_session}
+        ingameui::{system_clear_ingame_ui, system_weapon_ui, system_ingame_ui, setup_ingame_ui, system_round_announcement_ui, system_boss_health_ui},
+        player::{setup_player_camera, system_player_added},
+        spectator::{SpectatorCamera, system_spectator_camera, system_reset_spectator_camera},
+        camera::{PlayerCameraConfig, PlayerCameraState, system_camera_shake, system_camera_frame_local_players, system_reset_player_camera},
+        InGameSystemLabel,
+    }, p2p::{online::system_cleanup_network_session, schedule::{build_ggrs_plugin, rollback_schedule}, spectator::{system_spectator_relay, system_spectator_advance}}
 };
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{menu::lanmenu::LanMenuPlugin, p2p::{lan::LanArgs, sync_check::{sync_check_frames_from_args, run_sync_check}}, shared::map::validation::{MapValidationArgs, validate_map}};
+
 use bevy_kira_audio::AudioPlugin;
 
 const TIME_STEP: f32 = 1.0 / 60.0;
 
-const ROLLBACK_DEFAULT: &str = "rollback_default";
-const CHECKSUM_UPDATE: &str = "checksum_update";
-
 
 fn print_events_system(mut session: ResMut<P2PSession<GGRSConfig>>) {
     for event in session.events() {
@@ -58,6 +63,37 @@ fn print_events_system(mut session: ResMut<P2PSession<GGRSConfig>>) {
 fn main() {
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(frames) = sync_check_frames_from_args() {
+        match run_sync_check(frames) {
+            Ok(_) => println!("Sync check passed for {} frames", frames),
+            Err(err) => {
+                println!("Sync check failed : {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(args) = MapValidationArgs::from_args(DEFAULT_LEVEL) {
+        match validate_map(args.map.as_str(), args.level.as_str()) {
+            Ok(report) => {
+                report.print();
+                if report.has_errors() {
+                    println!("Map {} is invalid", args.map);
+                    std::process::exit(1);
+                }
+                println!("Map {} is valid", args.map);
+            },
+            Err(err) => {
+                println!("Map validation failed : {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
     
     let mut app = App::new();
 
@@ -65,82 +101,7 @@ fn main() {
     let game_speed = GameSpeed::default();
 
 
-    GGRSPlugin::<GGRSConfig>::new()
-        // define frequency of rollback game logic update
-        .with_update_frequency(game_speed.1)
-        // define system that returns inputs given a player handle, so GGRS can send the inputs around
-        .with_input_system(input)
-        // register types of components AND resources you want to be rolled back
-        .register_rollback_type::<Player>()
-        .register_rollback_type::<LookingAt>()
-        .register_rollback_type::<Transform>()
-        .register_rollback_type::<Velocity>()
-        .register_rollback_type::<FrameCount>()
-        .register_rollback_type::<AmmunitionState>()
-        .register_rollback_type::<Projectile>()
-        .register_rollback_type::<Zombie>()
-        .register_rollback_type::<ZombieGame>()
-        .register_rollback_type::<ZombieSpawner>()
-        .register_rollback_type::<CharacterMovementState>()
-        .register_rollback_type::<BotDestination>()
-        .register_rollback_type::<Health>()
-        .register_rollback_type::<ProjectileCollider>()
-        //.register_rollback_type::<HealthRegeneration>()
-        .register_rollback_type::<Death>()
-
-
-        // these systems will be executed as part of the advance frame update
-        .with_rollback_schedule(
-            Schedule::default().with_stage(
-                ROLLBACK_DEFAULT,
-                SystemStage::parallel()
-                    .with_system_set(
-                        SystemSet::new()
-                            .with_system(system_zombie_handle)
-                            .with_system(apply_input_players)
-                            .with_system(handle_weapon_input)
-                            .with_system(system_interaction_player)
-                            .label(P2PSystemLabel::Input)
-                    )
-                    .with_system_set(
-                        SystemSet::new()
-                            .with_system(update_velocity_player)
-                            .with_system(system_move_zombie)
-                            .label(P2PSystemLabel::Move)
-                            .after(P2PSystemLabel::Input)
-                    )
-                    .with_system_set(
-                        SystemSet::new()
-                            .with_system(move_players)
-                            .with_system(movement_projectile)
-                            .with_system(apply_velocity)
-                            .label(P2PSystemLabel::Collision)
-                            .after(P2PSystemLabel::Move)
-                    )
-                    .with_system_set(
-                        SystemSet::new()
-                            .with_system(system_health_player)
-                            .with_system(system_zombie_game)
-                            .after(P2PSystemLabel::Collision)
-                            .label(P2PSystemLabel::GameLogic)
-                    )
-                    .with_system_set(
-                        SystemSet::new()
-                            .with_system(increase_frame_system)
-                            .with_system(system_end_game)
-                            .after(P2PSystemLabel::GameLogic)
-                    )
-            )
-            .with_stage_after(
-                ROLLBACK_DEFAULT, 
-                CHECKSUM_UPDATE,
-                SystemStage::parallel()
-                        .with_system(checksum_zombie)
-                        .with_system(checksum_zombiegame)
-            ),
-        )
-        // make it happen in the bevy app
-        .build(&mut app);
+    build_ggrs_plugin(&mut app, &game_speed, input, rollback_schedule());
 
     // Create an GGRS session
     app.insert_resource(WindowDescriptor {
@@ -152,20 +113,35 @@ fn main() {
         canvas: Some("#bevy-canvas".to_string()),
         ..WindowDescriptor::default()
     })
-    .insert_resource(LevelMapRequested{map: "maps/map_iso/iso_map.asset.ron".to_string(), level: "game/easy.level.ron".to_string()})
+    .insert_resource(LevelMapRequested{map: DEFAULT_MAP.to_string(), level: DEFAULT_LEVEL.to_string()})
     .insert_resource(AvailableGameController{
         keyboard_mouse: true,
         gamepad: vec![]
     })
     .insert_resource(FrameCount { frame: 0 })
+    .init_resource::<SpectatorCamera>()
+    .init_resource::<PlayerCameraConfig>()
+    .init_resource::<PlayerCameraState>()
     .add_plugins(DefaultPlugins)
     .add_plugin(CharacterAnimationPlugin{ })
     .add_plugin(AudioPlugin{})
     .add_plugin(MapPlugin {});
 
     app.add_plugin(ZombieGamePlugin{});
+    app.add_plugin(WeaponAssetErrorUiPlugin{});
     app.add_plugin(HomeMenuPlugin{});
     app.add_plugin(OnlineMenuPlugin{});
+    app.add_plugin(LevelSelectPlugin{});
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        app.add_plugin(LanMenuPlugin{});
+        #[cfg(feature = "editor")]
+        app.add_plugin(editor::mapeditor::MapEditorPlugin{});
+        if let Some(lan_args) = LanArgs::from_args() {
+            app.insert_resource(lan_args);
+        }
+    }
 
     app.add_system(system_gamepad_event);
 
@@ -194,6 +170,17 @@ fn main() {
             .with_system(react_level_data)
             .with_system(system_player_added)
     )
+    .add_system_set(
+        SystemSet::on_update(GameState::PlayingZombie)
+            .with_system(system_spectator_camera)
+            .with_system(system_camera_shake.before(InGameSystemLabel::CameraFrame))
+            .with_system(system_camera_frame_local_players.label(InGameSystemLabel::CameraFrame))
+            .with_system(system_round_events.before(InGameSystemLabel::RoundAnnouncement))
+            .with_system(system_round_announcement_ui.label(InGameSystemLabel::RoundAnnouncement))
+            .with_system(system_boss_health_ui)
+            .with_system(system_spectator_relay)
+            .with_system(system_spectator_advance.exclusive_system())
+    )
     .add_system_set(
         SystemSet::on_exit(GameState::PlayingZombie)
             .with_system(system_unload_map)
@@ -201,6 +188,8 @@ fn main() {
             .with_system(system_unload_players)
             .with_system(system_unload_zombie_game)
             .with_system(system_cleanup_network_session)
+            .with_system(system_reset_spectator_camera)
+            .with_system(system_reset_player_camera)
     );
 
     //if opts.benchmark_mode {
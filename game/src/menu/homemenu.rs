@@ -1,10 +1,10 @@
 use bevy::{prelude::*, app::AppExit};
 
-use crate::{p2p::{online::{NetworkPlayer, create_session}}};
+use crate::{p2p::{online::{NetworkPlayer, create_session}, loopback::LoopbackNetwork}, menu::{onlinemenu::ConnectData, levelselect::{LevelCatalog, CatalogKind, add_level_selection}}};
 
 use super::ui_utils::*;
 use crate::shared::{
-    game::{GameState, ZombieGame, ZombiePlayerInformation, GameSpeed, ZombieGameConfig},
+    game::{GameState, ZombieGame, ZombiePlayerInformation, GameSpeed, ZombieGameConfig, LevelMapRequested},
     player::input::{AvailableGameController, PlayerCurrentInput, SupportedController}
 };
 
@@ -37,9 +37,15 @@ pub fn setup_home_menu(
                     ..default()
                 }).with_children(|parent| {
                     add_button(ActionButtonComponent(ButtonActions::QuitApplication), "Close", parent, &asset_server);
+                    #[cfg(all(feature = "editor", not(target_arch = "wasm32")))]
+                    add_button(ActionButtonComponent(ButtonActions::OpenMapEditor), "map editor", parent, &asset_server);
+                    add_button(ActionButtonComponent(ButtonActions::WatchOnlineGame), "watch online match", parent, &asset_server);
+                    #[cfg(not(target_arch = "wasm32"))]
+                    add_button(ActionButtonComponent(ButtonActions::StartLanMultiplayerGame), "LAN multiplayer", parent, &asset_server);
                     add_button(ActionButtonComponent(ButtonActions::StartOnlineMultiplayerGame), "online multiplayer", parent, &asset_server);
                     add_button(ActionButtonComponent(ButtonActions::StartLocalMultiplayerGame), "local multiplayer", parent, &asset_server);
                     add_button(ActionButtonComponent(ButtonActions::StartLocalGame), "single player", parent, &asset_server);
+                    add_level_selection(parent, &asset_server);
                 });
         });
 }
@@ -58,7 +64,10 @@ pub fn system_button_handle(
     mut app_state: ResMut<State<GameState>>,
 
     mut zombie_game: ResMut<ZombieGameConfig>,
-    controller: Res<AvailableGameController>
+    controller: Res<AvailableGameController>,
+
+    mut catalog: ResMut<LevelCatalog>,
+    mut level_requested: ResMut<LevelMapRequested>,
 ) {
     for (interaction, mut color, action) in interaction_query.iter_mut() {
         match *interaction {
@@ -78,11 +87,27 @@ pub fn system_button_handle(
 
                         let mut players: Vec<NetworkPlayer> = vec![];
                         players.push(NetworkPlayer{address: "localhost".to_string()});
-                        create_session(&mut commands, &game_speed, players);
+                        // every player is local , the socket never send anything
+                        create_session(&mut commands, &game_speed, players, LoopbackNetwork::default().socket("localhost"));
 
                         app_state.set(GameState::PlayingZombie).unwrap();
                     },
                     ButtonActions::StartOnlineMultiplayerGame => {
+                        catalog.select_default(&mut level_requested);
+                        commands.insert_resource(ConnectData::default());
+                        app_state.set(GameState::OnlineMenu).unwrap();
+                    },
+                    ButtonActions::StartLanMultiplayerGame => {
+                        catalog.select_default(&mut level_requested);
+                        app_state.set(GameState::LanMenu).unwrap();
+                    },
+                    #[cfg(all(feature = "editor", not(target_arch = "wasm32")))]
+                    ButtonActions::OpenMapEditor => {
+                        app_state.set(GameState::MapEditor).unwrap();
+                    },
+                    ButtonActions::WatchOnlineGame => {
+                        catalog.select_default(&mut level_requested);
+                        commands.insert_resource(ConnectData { spectate: true, ..default() });
                         app_state.set(GameState::OnlineMenu).unwrap();
                     },
                     ButtonActions::StartLocalMultiplayerGame => {
@@ -108,11 +133,16 @@ pub fn system_button_handle(
                             })
                         }
 
-                        create_session(&mut commands, &game_speed, players);
+                        // every player is local , the socket never send anything
+                        create_session(&mut commands, &game_speed, players, LoopbackNetwork::default().socket("localhost"));
 
                         app_state.set(GameState::PlayingZombie).unwrap();
 
                     },
+                    ButtonActions::PreviousMap => catalog.select(CatalogKind::Map, -1, &mut level_requested),
+                    ButtonActions::NextMap => catalog.select(CatalogKind::Map, 1, &mut level_requested),
+                    ButtonActions::PreviousLevel => catalog.select(CatalogKind::Level, -1, &mut level_requested),
+                    ButtonActions::NextLevel => catalog.select(CatalogKind::Level, 1, &mut level_requested),
                     ButtonActions::QuitApplication => {
                         exit.send(AppExit);
                     },
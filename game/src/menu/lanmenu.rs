@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+
+use crate::p2p::lan::{LanArgs, start_lan_game, LAN_HOST_PORT, LAN_JOIN_PORT};
+use crate::shared::game::{GameState, GameSpeed, ZombieGameConfig, LevelMapRequested};
+
+use super::levelselect::LevelCatalog;
+
+use super::ui_utils::*;
+
+
+#[derive(Component)]
+pub struct MenuLanUI;
+
+#[derive(Component)]
+pub struct LanTitleText;
+
+#[derive(Component)]
+pub struct LanAddressText;
+
+#[derive(Component)]
+pub struct LanStatusText;
+
+// address of the other player typed in the menu , the port of the other
+// side is used when only the ip is given
+pub struct LanConnectData {
+    pub peer_address: String,
+}
+
+impl Default for LanConnectData {
+	fn default() -> Self {
+		Self {
+			peer_address: "127.0.0.1".to_string(),
+		}
+	}
+}
+
+impl LanConnectData {
+    pub fn address_with_port(&self, default_port: u16) -> String {
+        if self.peer_address.contains(':') {
+            self.peer_address.clone()
+        } else {
+            format!("{}:{}", self.peer_address, default_port)
+        }
+    }
+}
+
+pub fn setup_lan_ui(mut commands: Commands, asset_server: Res<AssetServer>, connect_data: Res<LanConnectData>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect::all(Val::Px(0.)),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_content: AlignContent::Center,
+                align_items: AlignItems::Center,
+                align_self: AlignSelf::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            add_text(LanTitleText, "Other player address (ip or ip:port)", 32., parent, &asset_server);
+            add_text(LanAddressText, connect_data.peer_address.as_str(), 32., parent, &asset_server);
+
+            add_button(ActionButtonComponent(ButtonActions::HostLanGame), &format!("Host (:{})", LAN_HOST_PORT), parent, &asset_server);
+            add_button(ActionButtonComponent(ButtonActions::JoinLanGame), &format!("Join (:{})", LAN_JOIN_PORT), parent, &asset_server);
+            add_button(ActionButtonComponent(ButtonActions::BackToMenu), "Back to Menu", parent, &asset_server);
+
+            add_text(LanStatusText, "", 32., parent, &asset_server);
+        })
+        .insert(MenuLanUI);
+}
+
+pub fn system_lan_address_input(
+    mut char_evr: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut connect_data: ResMut<LanConnectData>,
+    mut q_text: Query<&mut Text, With<LanAddressText>>,
+) {
+    let mut changed = false;
+    for ev in char_evr.iter() {
+        if ev.char.is_ascii_digit() || ev.char == '.' || ev.char == ':' {
+            connect_data.peer_address.push(ev.char);
+            changed = true;
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        connect_data.peer_address.pop();
+        changed = true;
+    }
+
+    if changed {
+        for mut text in q_text.iter_mut() {
+            text.sections[0].value = connect_data.peer_address.clone();
+        }
+    }
+}
+
+pub fn btn_lan_visuals(
+    mut interaction_query: Query<
+        (&Interaction, &mut UiColor),
+        (Changed<Interaction>, With<ActionButtonComponent>),
+    >,
+) {
+    for (interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = PRESSED_BUTTON.into();
+            }
+            Interaction::Hovered => {
+                *color = HOVERED_BUTTON.into();
+            }
+            Interaction::None => {
+                *color = NORMAL_BUTTON.into();
+            }
+        }
+    }
+}
+
+pub fn btn_lan_listeners(
+    mut commands: Commands,
+    game_speed: Res<GameSpeed>,
+    connect_data: Res<LanConnectData>,
+    mut zombie_game: ResMut<ZombieGameConfig>,
+
+    mut state: ResMut<State<GameState>>,
+    mut interaction_query: Query<(&Interaction, &ActionButtonComponent), Changed<Interaction>>,
+    mut q_status_text: Query<&mut Text, With<LanStatusText>>,
+) {
+    for (interaction, btn) in interaction_query.iter_mut() {
+        if let Interaction::Clicked = *interaction {
+            // the host connect to the port of the joiner and the joiner to the port of the host
+            let (index, port, peer_port) = match btn.0 {
+                ButtonActions::HostLanGame => (0, LAN_HOST_PORT, LAN_JOIN_PORT),
+                ButtonActions::JoinLanGame => (1, LAN_JOIN_PORT, LAN_HOST_PORT),
+                ButtonActions::BackToMenu => {
+                    state
+                        .set(GameState::Menu)
+                        .expect("Could not change state.");
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let peer_address = connect_data.address_with_port(peer_port);
+            match start_lan_game(&mut commands, &game_speed, &mut zombie_game, index, port, peer_address.as_str()) {
+                Ok(_) => {
+                    state
+                        .set(GameState::PlayingZombie)
+                        .expect("Could not change state.");
+                },
+                Err(err) => {
+                    println!("{}", err);
+                    for mut text in q_status_text.iter_mut() {
+                        text.sections[0].value = err.clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Start the LAN game directly when the options are pass on the command line
+pub fn system_start_lan_from_args(
+    mut commands: Commands,
+    game_speed: Res<GameSpeed>,
+    lan_args: Option<Res<LanArgs>>,
+    mut zombie_game: ResMut<ZombieGameConfig>,
+    mut catalog: ResMut<LevelCatalog>,
+    mut level_requested: ResMut<LevelMapRequested>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if let Some(lan_args) = lan_args {
+        catalog.select_default(&mut level_requested);
+        match start_lan_game(&mut commands, &game_speed, &mut zombie_game, lan_args.index, lan_args.port, lan_args.remote_host.as_str()) {
+            Ok(_) => {
+                state.set(GameState::PlayingZombie).unwrap();
+            },
+            Err(err) => println!("{}", err)
+        }
+        commands.remove_resource::<LanArgs>();
+    }
+}
+
+pub fn cleanup_lan_ui(query: Query<Entity, With<MenuLanUI>>, mut commands: Commands) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct LanMenuPlugin {}
+
+impl Plugin for LanMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .init_resource::<LanConnectData>()
+        .add_system_set(
+            SystemSet::on_enter(GameState::Menu)
+                .with_system(system_start_lan_from_args)
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::LanMenu)
+                .with_system(setup_lan_ui)
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::LanMenu)
+                .with_system(btn_lan_visuals)
+                .with_system(btn_lan_listeners)
+                .with_system(system_lan_address_input)
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::LanMenu)
+                .with_system(cleanup_lan_ui)
+        );
+    }
+}
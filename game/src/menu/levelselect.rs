@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+
+use crate::shared::{
+    game::{AssetMetadata, GameState, LevelMapRequested, ZombieLevelAsset, DEFAULT_MAP, DEFAULT_LEVEL},
+    map::render::MapDataAsset,
+};
+
+use super::ui_utils::*;
+
+const MAP_ASSET_EXTENSION: &str = ".asset.ron";
+const LEVEL_ASSET_EXTENSION: &str = ".level.ron";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CatalogKind {
+    Map,
+    Level,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CatalogField {
+    Name,
+    Description,
+}
+
+// Text of the level selection , updated with the metadata of the selected asset
+#[derive(Component)]
+pub struct LevelSelectionText(pub CatalogKind, pub CatalogField);
+
+#[derive(Component)]
+pub struct LevelSelectionPreview(pub CatalogKind);
+
+pub struct CatalogEntry<T: bevy::asset::Asset> {
+    pub path: String,
+    pub handle: Handle<T>,
+}
+
+// Every map and level of the assets folder , with the selected pair
+#[derive(Default)]
+pub struct LevelCatalog {
+    pub maps: Vec<CatalogEntry<MapDataAsset>>,
+    pub levels: Vec<CatalogEntry<ZombieLevelAsset>>,
+    pub selected_map: usize,
+    pub selected_level: usize,
+}
+
+impl LevelCatalog {
+    pub fn select(&mut self, kind: CatalogKind, offset: i32, requested: &mut LevelMapRequested) {
+        match kind {
+            CatalogKind::Map => {
+                if self.maps.len() == 0 { return; }
+                self.selected_map = (self.selected_map as i32 + offset).rem_euclid(self.maps.len() as i32) as usize;
+                requested.map = self.maps[self.selected_map].path.clone();
+            },
+            CatalogKind::Level => {
+                if self.levels.len() == 0 { return; }
+                self.selected_level = (self.selected_level as i32 + offset).rem_euclid(self.levels.len() as i32) as usize;
+                requested.level = self.levels[self.selected_level].path.clone();
+            },
+        }
+    }
+
+    // The selection is not exchanged between the peers , the network games are
+    // locked on the default pair so every peer load the same map and level
+    pub fn select_default(&mut self, requested: &mut LevelMapRequested) {
+        self.selected_map = self.maps.iter().position(|x| x.path == DEFAULT_MAP).unwrap_or(0);
+        self.selected_level = self.levels.iter().position(|x| x.path == DEFAULT_LEVEL).unwrap_or(0);
+        requested.map = DEFAULT_MAP.to_string();
+        requested.level = DEFAULT_LEVEL.to_string();
+    }
+}
+
+// Path of the files of the assets folder ending with `extension` , relative to the folder
+#[cfg(not(target_arch = "wasm32"))]
+fn list_assets(extension: &str) -> Vec<String> {
+    fn visit(root: &std::path::Path, dir: &std::path::Path, extension: &str, paths: &mut Vec<String>) {
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    visit(root, &path, extension, paths);
+                } else if let Ok(relative) = path.strip_prefix(root) {
+                    let relative = relative.to_string_lossy().replace('\\', "/");
+                    if relative.ends_with(extension) {
+                        paths.push(relative);
+                    }
+                }
+            }
+        }
+    }
+
+    let root = crate::shared::utils::asset_root();
+    let mut paths = vec![];
+    visit(&root, &root, extension, &mut paths);
+    paths.sort();
+    paths
+}
+
+// The assets folder can't be read from the browser , only the default pair is available
+#[cfg(target_arch = "wasm32")]
+fn list_assets(extension: &str) -> Vec<String> {
+    match extension {
+        MAP_ASSET_EXTENSION => vec![DEFAULT_MAP.to_string()],
+        _ => vec![DEFAULT_LEVEL.to_string()],
+    }
+}
+
+pub fn setup_level_catalog(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut requested: ResMut<LevelMapRequested>,
+) {
+    let mut catalog = LevelCatalog::default();
+
+    for path in list_assets(MAP_ASSET_EXTENSION) {
+        catalog.maps.push(CatalogEntry { handle: asset_server.load(path.as_str()), path });
+    }
+    for path in list_assets(LEVEL_ASSET_EXTENSION) {
+        catalog.levels.push(CatalogEntry { handle: asset_server.load(path.as_str()), path });
+    }
+
+    let default_map = if requested.map.is_empty() { DEFAULT_MAP } else { requested.map.as_str() };
+    let default_level = if requested.level.is_empty() { DEFAULT_LEVEL } else { requested.level.as_str() };
+    catalog.selected_map = catalog.maps.iter().position(|x| x.path == default_map).unwrap_or(0);
+    catalog.selected_level = catalog.levels.iter().position(|x| x.path == default_level).unwrap_or(0);
+    catalog.select(CatalogKind::Map, 0, &mut requested);
+    catalog.select(CatalogKind::Level, 0, &mut requested);
+
+    commands.insert_resource(catalog);
+}
+
+fn add_selection_text(
+    marker: LevelSelectionText,
+    font_size: f32,
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+) {
+    parent.spawn()
+        .insert(marker)
+        .insert_bundle(TextBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: font_size,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                default(),
+            ),
+            ..default()
+        });
+}
+
+fn add_selector(
+    kind: CatalogKind,
+    previous: ButtonActions,
+    next: ButtonActions,
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+) {
+    parent.spawn()
+        .insert_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                margin: Rect::all(Val::Px(10.)),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn()
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    add_small_button(ActionButtonComponent(previous), "<", parent, asset_server);
+                    add_selection_text(LevelSelectionText(kind, CatalogField::Name), 30., parent, asset_server);
+                    add_small_button(ActionButtonComponent(next), ">", parent, asset_server);
+                });
+            add_selection_text(LevelSelectionText(kind, CatalogField::Description), 18., parent, asset_server);
+            parent.spawn()
+                .insert(LevelSelectionPreview(kind))
+                .insert_bundle(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(200.), Val::Px(120.)),
+                        ..default()
+                    },
+                    ..default()
+                });
+        });
+}
+
+// Map and level selectors of the home menu
+pub fn add_level_selection(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
+    add_selector(CatalogKind::Map, ButtonActions::PreviousMap, ButtonActions::NextMap, parent, asset_server);
+    add_selector(CatalogKind::Level, ButtonActions::PreviousLevel, ButtonActions::NextLevel, parent, asset_server);
+}
+
+fn display_metadata(metadata: Option<&AssetMetadata>, path: &str, index: usize, total: usize, field: CatalogField) -> String {
+    match field {
+        CatalogField::Name => {
+            let name = metadata.map(|x| x.name.as_str()).filter(|x| !x.is_empty()).unwrap_or(path);
+            format!("{} ({}/{})", name, index + 1, total)
+        },
+        CatalogField::Description => metadata.map(|x| x.description.clone()).unwrap_or_default(),
+    }
+}
+
+pub fn system_level_selection_ui(
+    catalog: Res<LevelCatalog>,
+    asset_server: Res<AssetServer>,
+    maps: Res<Assets<MapDataAsset>>,
+    levels: Res<Assets<ZombieLevelAsset>>,
+
+    mut q_text: Query<(&mut Text, &LevelSelectionText)>,
+    mut q_preview: Query<(&mut UiImage, &mut Visibility, &LevelSelectionPreview)>,
+) {
+    let map = catalog.maps.get(catalog.selected_map);
+    let level = catalog.levels.get(catalog.selected_level);
+    let map_metadata = map.and_then(|x| maps.get(&x.handle)).map(|x| &x.metadata);
+    let level_metadata = level.and_then(|x| levels.get(&x.handle)).map(|x| &x.metadata);
+
+    for (mut text, selection) in q_text.iter_mut() {
+        let value = match (selection.0, map, level) {
+            (CatalogKind::Map, Some(map), _) => display_metadata(map_metadata, map.path.as_str(), catalog.selected_map, catalog.maps.len(), selection.1),
+            (CatalogKind::Level, _, Some(level)) => display_metadata(level_metadata, level.path.as_str(), catalog.selected_level, catalog.levels.len(), selection.1),
+            _ => "none".to_string(),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+
+    for (mut image, mut visibility, preview) in q_preview.iter_mut() {
+        let metadata = match preview.0 {
+            CatalogKind::Map => map_metadata,
+            CatalogKind::Level => level_metadata,
+        };
+        match metadata.and_then(|x| x.preview.as_ref()) {
+            Some(path) => {
+                let handle: Handle<Image> = asset_server.load(path.as_str());
+                visibility.is_visible = true;
+                if image.0 != handle {
+                    image.0 = handle;
+                }
+            },
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+pub struct LevelSelectPlugin {}
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_startup_system(setup_level_catalog)
+        .add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(system_level_selection_ui)
+        );
+    }
+}
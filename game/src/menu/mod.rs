@@ -1,4 +1,7 @@
 pub mod homemenu;
 pub mod localmultiplayerui;
 pub mod ui_utils;
-pub mod onlinemenu;
\ No newline at end of file
+pub mod onlinemenu;
+pub mod levelselect;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lanmenu;
\ No newline at end of file
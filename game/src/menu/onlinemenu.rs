@@ -1,10 +1,10 @@
 use bevy::{prelude::*, tasks::IoTaskPool};
 use bevy_ggrs::SessionType;
-use ggrs::{PlayerHandle,SessionBuilder};
+use ggrs::{PlayerHandle, PlayerType, SessionBuilder};
 use matchbox_socket::WebRtcSocket;
 use crate::shared::{game::{GameState, ZombiePlayerInformation, GameSpeed, ZombieGameConfig}, player::input::{SupportedController, GGRSConfig, PlayerCurrentInput}};
 
-use crate::p2p::config::{MAX_PREDICTION, INPUT_DELAY, CHECK_DISTANCE};
+use crate::p2p::{config::{MAX_PREDICTION, INPUT_DELAY, CHECK_DISTANCE, NUM_ONLINE_PLAYERS}, spectator::{SpectatorClient, SpectatorRelay, SPECTATOR_ROOM_SUFFIX}};
 
 use super::ui_utils::{HOVERED_BUTTON, NORMAL_BUTTON, PRESSED_BUTTON};
 
@@ -27,44 +27,81 @@ pub struct LocalHandles {
 
 pub struct ConnectData {
     pub lobby_id: String,
+    // join the spectator room of the lobby to watch the match instead of playing it
+    pub spectate: bool,
 }
 
 impl Default for ConnectData {
 	fn default() -> Self {
 		Self {
-			lobby_id: "test".to_string()
+			lobby_id: "test".to_string(),
+			spectate: false,
 		}
 	}
 }
 
+fn create_room_socket(task_pool: &IoTaskPool, room_url: String) -> WebRtcSocket {
+    let (socket, message_loop) = WebRtcSocket::new(room_url);
+    task_pool.spawn(message_loop).detach();
+    socket
+}
+
 pub fn create_matchbox_socket(
     mut commands: Commands,
     connect_data: Res<ConnectData>,
     task_pool: Res<IoTaskPool>,
 ) {
     let lobby_id = &connect_data.lobby_id;
-    let room_url = format!("{MATCHBOX_ADDR}/{lobby_id}");
-    let (socket, message_loop) = WebRtcSocket::new(room_url);
-    task_pool.spawn(message_loop).detach();
-    commands.insert_resource(Some(socket));
-    commands.remove_resource::<ConnectData>();
+    if connect_data.spectate {
+        // spectators have their own room , they never take the place of a player
+        let socket = create_room_socket(&task_pool, format!("{MATCHBOX_ADDR}/{lobby_id}{SPECTATOR_ROOM_SUFFIX}"));
+        commands.insert_resource(SpectatorClient::new(socket));
+        commands.insert_resource::<Option<WebRtcSocket>>(None);
+    } else {
+        let socket = create_room_socket(&task_pool, format!("{MATCHBOX_ADDR}/{lobby_id}"));
+        commands.insert_resource(Some(socket));
+    }
+}
+
+fn online_player_information(handle: PlayerHandle) -> ZombiePlayerInformation {
+    ZombiePlayerInformation {
+        name: format!("Player {}", handle),
+        controller: PlayerCurrentInput { input_source: SupportedController::Keyboard,  ..default() },
+        index: handle,
+        is_local: false,
+    }
 }
 
 pub fn update_matchbox_socket(
     mut commands: Commands,
 	game_speed: Res<GameSpeed>,
+    task_pool: Res<IoTaskPool>,
     mut socket_res: ResMut<Option<WebRtcSocket>>,
+	spectator: Option<ResMut<SpectatorClient>>,
+	connect_data: Res<ConnectData>,
 
 	mut zombie_game: ResMut<ZombieGameConfig>,
 
     mut app_state: ResMut<State<GameState>>,
 ) {
+	// A spectator wait for the host to relay the match , it can join at any time
+	if let Some(mut spectator) = spectator {
+		spectator.receive_frames();
+		if spectator.is_ready() {
+			zombie_game.players = (0..NUM_ONLINE_PLAYERS).map(online_player_information).collect();
+			commands.insert_resource(LocalHandles { handles: vec![] });
+			app_state.set(GameState::PlayingZombie).unwrap();
+		}
+		return;
+	}
+
     if let Some(socket) = socket_res.as_mut() {
         socket.accept_new_connections();
-        if socket.players().len() >= 2 {
-			
+
+        if socket.players().len() >= NUM_ONLINE_PLAYERS {
+
 			let mut sess_build = SessionBuilder::<GGRSConfig>::new()
-        		.with_num_players(2)
+        		.with_num_players(NUM_ONLINE_PLAYERS)
         		.with_max_prediction_window(MAX_PREDICTION)
         		.with_fps(game_speed.1)
         		.expect("Invalid FPS")
@@ -73,20 +110,22 @@ pub fn update_matchbox_socket(
 
 			let socket: WebRtcSocket = socket_res.as_mut().take().unwrap();
 
-			let mut handles = Vec::new();
+			let handles = Vec::new();
+
+			// the players are sorted the same way by every peer , the player 0 is the host
+			// of the match and the only one relaying it to the spectators
+			let mut is_host = false;
+
+			for (i, player_type) in socket.players().iter().take(NUM_ONLINE_PLAYERS).enumerate() {
+				if i == 0 && matches!(player_type, PlayerType::Local) {
+					is_host = true;
+				}
 
-			for (i, player_type) in socket.players().iter().enumerate() {
-				let mut is_local = false;
 				//if *player_type == PlayerType::Local {
 				//	handles.push(i);
 				//	is_local = true;
 				//}
-				zombie_game.players.push(ZombiePlayerInformation {
-					name: format!("Player {}", i),
-					controller: PlayerCurrentInput { input_source: SupportedController::Keyboard,  ..default() },
-					index: i,
-					is_local,
-				});
+				zombie_game.players.push(online_player_information(i));
 				sess_build = sess_build.add_player(player_type.clone(), i)
 					.expect("Invalid player added.");
 			}
@@ -98,14 +137,21 @@ pub fn update_matchbox_socket(
         		.expect("Session could not be created.");
 
 		    commands.insert_resource(sess);
-		    commands.insert_resource(LocalHandles { handles });
 		    commands.insert_resource(SessionType::P2PSession);
+		    commands.insert_resource(LocalHandles { handles });
+
+			if is_host {
+				let lobby_id = &connect_data.lobby_id;
+				let spectator_socket = create_room_socket(&task_pool, format!("{MATCHBOX_ADDR}/{lobby_id}{SPECTATOR_ROOM_SUFFIX}"));
+				commands.insert_resource(SpectatorRelay::new(spectator_socket));
+			}
         }
     }
 }
 
 pub fn cleanup(mut commands: Commands) {
     commands.remove_resource::<Option<WebRtcSocket>>();
+    commands.remove_resource::<ConnectData>();
 }
 
 pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -200,6 +246,7 @@ pub fn btn_visuals(
 }
 
 pub fn btn_listeners(
+    mut commands: Commands,
     mut state: ResMut<State<GameState>>,
     mut interaction_query: Query<(&Interaction, &MenuConnectBtn), Changed<Interaction>>,
 ) {
@@ -207,6 +254,7 @@ pub fn btn_listeners(
         if let Interaction::Clicked = *interaction {
             match btn {
                 MenuConnectBtn::Back => {
+                    commands.remove_resource::<SpectatorClient>();
                     state
                         .set(GameState::Menu)
                         .expect("Could not change state.");
@@ -227,7 +275,6 @@ pub struct OnlineMenuPlugin {}
 impl Plugin for OnlineMenuPlugin{
     fn build(&self, app: &mut App) {
         app
-		.init_resource::<ConnectData>()
         .add_system_set(
             SystemSet::on_enter(GameState::OnlineMenu)
                 .with_system(setup_ui)
@@ -14,6 +14,17 @@ pub enum ButtonActions {
     StartLocalGame,
     StartLocalMultiplayerGame,
     StartOnlineMultiplayerGame,
+    WatchOnlineGame,
+    StartLanMultiplayerGame,
+    HostLanGame,
+    JoinLanGame,
+    BackToMenu,
+    PreviousMap,
+    NextMap,
+    PreviousLevel,
+    NextLevel,
+    #[cfg(all(feature = "editor", not(target_arch = "wasm32")))]
+    OpenMapEditor,
     QuitApplication
 }
 
@@ -26,13 +37,55 @@ pub fn add_button(
     text: &str,
     parent: &mut ChildBuilder,
     asset_server: &Res<AssetServer>,
+) {
+    add_button_with_size(action, text, Size::new(Val::Px(400.0), Val::Px(65.0)), parent, asset_server);
+}
+
+pub fn add_text(
+    marker: impl Component,
+    text: &str,
+    font_size: f32,
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+) {
+    parent.spawn()
+        .insert(marker)
+        .insert_bundle(TextBundle {
+            text: Text::with_section(
+                text,
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: font_size,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                default(),
+            ),
+            ..default()
+        });
+}
+
+pub fn add_small_button(
+    action: ActionButtonComponent,
+    text: &str,
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+) {
+    add_button_with_size(action, text, Size::new(Val::Px(50.0), Val::Px(50.0)), parent, asset_server);
+}
+
+fn add_button_with_size(
+    action: ActionButtonComponent,
+    text: &str,
+    size: Size<Val>,
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
 ) {
     parent.spawn()
         .insert(MenuComponent{})
         .insert(action)
         .insert_bundle(ButtonBundle {
             style: Style {
-                size: Size::new(Val::Px(400.0), Val::Px(65.0)),
+                size: size,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                ..default()
@@ -1,7 +1,7 @@
 
 use bevy::prelude::*;
 use bevy_ggrs::Rollback;
-use crate::shared::{zombies::zombie::Zombie, utils::{Checksum, fletcher16}, game::ZombieGame};
+use crate::shared::{zombies::zombie::Zombie, utils::{Checksum, fletcher16}, game::ZombieGame, player::Player, health::Health, character::Velocity, weapons::weapons::{AmmunitionState, Projectile, WeaponRecoil, Weapon}};
 
 
 
@@ -27,9 +27,56 @@ pub fn checksum_zombiegame(
 ) {
     for (zombie_game, mut checksum) in query.iter_mut() {
         let mut bytes = Vec::with_capacity(28);
-        bytes.extend_from_slice(&[zombie_game.current_round.total_zombie as u8, zombie_game.current_round.zombie_remaining as u8, zombie_game.round as u8]);
+        bytes.extend_from_slice(&[zombie_game.current_round.total_zombie as u8, zombie_game.current_round.zombie_remaining as u8, zombie_game.round as u8, zombie_game.countdown as u8, zombie_game.spawn_countdown as u8, zombie_game.state.clone() as u8, zombie_game.special_round.map(|x| x as u8 + 1).unwrap_or(0)]);
+        bytes.extend_from_slice(&zombie_game.points.to_le_bytes());
 
         // naive checksum implementation
         checksum.value = fletcher16(&bytes);
     }
 }
+
+pub fn checksum_player(
+    mut query: Query<(&Transform, &Player, &Health, &mut Checksum), With<Rollback>>,
+) {
+    for (t, player, health, mut checksum) in query.iter_mut() {
+        let mut bytes = Vec::with_capacity(28);
+        bytes.extend_from_slice(&t.translation.x.to_le_bytes());
+        bytes.extend_from_slice(&t.translation.y.to_le_bytes());
+        bytes.extend_from_slice(&(player.handle as u32).to_le_bytes());
+        bytes.extend_from_slice(&health.current_health.to_le_bytes());
+        bytes.extend_from_slice(&health.tmp_health.to_le_bytes());
+
+        checksum.value = fletcher16(&bytes);
+    }
+}
+
+pub fn checksum_ammunition(
+    mut query: Query<(&Weapon, &AmmunitionState, &WeaponRecoil, &mut Checksum), With<Rollback>>,
+) {
+    for (weapon, ammunition_state, recoil, mut checksum) in query.iter_mut() {
+        let mut bytes = Vec::with_capacity(12 + weapon.name.len());
+        bytes.extend_from_slice(&ammunition_state.mag_remaining.to_le_bytes());
+        bytes.extend_from_slice(&ammunition_state.remaining_ammunition.to_le_bytes());
+        bytes.extend_from_slice(&recoil.current.to_le_bytes());
+        // the upgrade stations replace the weapon
+        bytes.extend_from_slice(weapon.name.as_bytes());
+
+        checksum.value = fletcher16(&bytes);
+    }
+}
+
+pub fn checksum_projectile(
+    mut query: Query<(&Transform, &Velocity, &Projectile, &mut Checksum), With<Rollback>>,
+) {
+    for (t, velocity, projectile, mut checksum) in query.iter_mut() {
+        let mut bytes = Vec::with_capacity(24);
+        bytes.extend_from_slice(&t.translation.x.to_le_bytes());
+        bytes.extend_from_slice(&t.translation.y.to_le_bytes());
+        bytes.extend_from_slice(&velocity.v.x.to_le_bytes());
+        bytes.extend_from_slice(&velocity.v.y.to_le_bytes());
+        bytes.extend_from_slice(&projectile.damage.to_le_bytes());
+        bytes.extend_from_slice(&projectile.penetration.to_le_bytes());
+
+        checksum.value = fletcher16(&bytes);
+    }
+}
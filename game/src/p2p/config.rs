@@ -6,6 +6,8 @@ pub const CHECKSUM_UPDATE: &str = "checksum_update";
 pub const MAX_PREDICTION: usize = 6;
 pub const INPUT_DELAY: usize = 4;
 pub const CHECK_DISTANCE: usize = 4;
+// number of players in a online match , other peers of the lobby are spectators
+pub const NUM_ONLINE_PLAYERS: usize = 2;
 
 
 pub struct LocalHandles {
@@ -26,6 +28,9 @@ pub enum P2PSystemLabel {
     // Game logic , spawn new entity , etc ...
     GameLogic,
 
+    // Update of the round state , the game logic reading it run after
+    ZombieGame,
+
     // Frame clean up , validate game state and change state
     FrameCleanup
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use crate::shared::{
+	game::{GameSpeed, ZombieGameConfig, ZombiePlayerInformation},
+	player::input::{PlayerCurrentInput, SupportedController},
+};
+
+use super::{online::{NetworkPlayer, create_session}, udp::{LanSocket, parse_address}};
+
+pub const LAN_HOST_PORT: u16 = 7000;
+pub const LAN_JOIN_PORT: u16 = 7001;
+
+// Options to start a LAN game directly from the command line
+// ex: --remote-host 127.0.0.1:7001 --port 7000 --index 0
+pub struct LanArgs {
+	pub remote_host: String,
+	pub port: u16,
+	pub index: usize,
+}
+
+impl LanArgs {
+	pub fn from_args() -> Option<LanArgs> {
+		let args: Vec<String> = std::env::args().collect();
+		let get_arg = |name: &str| args.iter().position(|x| x == name).and_then(|i| args.get(i + 1)).cloned();
+
+		let remote_host = get_arg("--remote-host")?;
+		let port = get_arg("--port")?.parse::<u16>().ok()?;
+		let index = get_arg("--index").and_then(|x| x.parse::<usize>().ok()).unwrap_or(0);
+
+		Some(LanArgs { remote_host, port, index })
+	}
+}
+
+// Create a two players session over UDP , the local player use the handle `local_index`
+// and the other player is reach at `remote_address`
+pub fn start_lan_game(
+	commands: &mut Commands,
+	game_speed: &GameSpeed,
+	zombie_game: &mut ZombieGameConfig,
+	local_index: usize,
+	local_port: u16,
+	remote_address: &str,
+) -> Result<(), String> {
+	let remote_address = parse_address(remote_address).ok_or(format!("Invalid address {}", remote_address))?;
+	let socket = LanSocket::bind_to_port(local_port).map_err(|err| format!("Failed to bind port {} : {}", local_port, err))?;
+
+	let mut players: Vec<NetworkPlayer> = vec![];
+	zombie_game.players = vec![];
+
+	for i in 0..2 {
+		let is_local = i == local_index;
+		players.push(NetworkPlayer { address: if is_local { "localhost".to_string() } else { remote_address.clone() } });
+		zombie_game.players.push(ZombiePlayerInformation {
+			name: format!("Player {}", i + 1),
+			controller: PlayerCurrentInput { input_source: SupportedController::Keyboard, gamepad: None, ..default() },
+			index: i,
+			is_local,
+		});
+	}
+
+	create_session(commands, game_speed, players, socket);
+
+	Ok(())
+}
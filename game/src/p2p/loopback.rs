@@ -0,0 +1,43 @@
+use std::{collections::{HashMap, VecDeque}, sync::{Arc, Mutex}};
+
+use ggrs::{Message, NonBlockingSocket};
+
+type LoopbackQueues = Arc<Mutex<HashMap<String, VecDeque<(String, Message)>>>>;
+
+// In process network to connect many sessions together , each socket
+// is reachable with the address given when created
+#[derive(Default, Clone)]
+pub struct LoopbackNetwork {
+	queues: LoopbackQueues,
+}
+
+impl LoopbackNetwork {
+	pub fn socket(&self, address: &str) -> LoopbackSocket {
+		self.queues.lock().unwrap().entry(address.to_string()).or_default();
+		LoopbackSocket {
+			address: address.to_string(),
+			queues: self.queues.clone(),
+		}
+	}
+}
+
+pub struct LoopbackSocket {
+	address: String,
+	queues: LoopbackQueues,
+}
+
+impl NonBlockingSocket<String> for LoopbackSocket {
+
+	fn send_to(&mut self, msg: &Message, addr: &String) {
+		if let Some(queue) = self.queues.lock().unwrap().get_mut(addr) {
+			queue.push_back((self.address.clone(), msg.clone()));
+		}
+	}
+
+	fn receive_all_messages(&mut self) -> Vec<(String, Message)> {
+		match self.queues.lock().unwrap().get_mut(&self.address) {
+			Some(queue) => queue.drain(..).collect(),
+			None => vec![],
+		}
+	}
+}
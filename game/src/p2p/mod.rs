@@ -1,3 +1,12 @@
 pub mod config;
 pub mod online;
-pub mod checksum;
\ No newline at end of file
+pub mod checksum;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod udp;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lan;
+pub mod schedule;
+pub mod loopback;
+pub mod spectator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync_check;
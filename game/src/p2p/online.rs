@@ -4,47 +4,20 @@ use bevy::prelude::*;
 use crate::shared::{game::GameSpeed, player::input::GGRSConfig};
 
 use bevy_ggrs::SessionType;
-use ggrs::{PlayerType, SessionBuilder, NonBlockingSocket, P2PSession, Message};
+use ggrs::{PlayerType, SessionBuilder, NonBlockingSocket, P2PSession};
 
-use super::config::{MAX_PREDICTION, INPUT_DELAY, CHECK_DISTANCE, LocalHandles};
-
-
-pub struct LocalSocket {
-	messages: Vec<(String, Message)>,
-}
-
-impl Default for LocalSocket {
-	fn default() -> Self {
-		Self {
-			messages: vec![]
-		}
-	}
-
-}
-
-impl ggrs::NonBlockingSocket<String> for LocalSocket {
-
-	fn send_to(&mut self, msg: &Message, addr: &String) {
-		self.messages.push((addr.clone(), msg.clone()));
-    }
-
-    fn receive_all_messages(&mut self) -> Vec<(String, Message)> {
-		let msg = self.messages.clone();
-		self.messages = vec![];
-        msg
-    }
-}
+use super::{config::{MAX_PREDICTION, INPUT_DELAY, CHECK_DISTANCE, LocalHandles}, spectator::{SpectatorRelay, SpectatorClient}};
 
 
 pub struct NetworkPlayer {
 	pub address: String,
 }
 
-pub fn create_session(
-	commands: &mut Commands,
+pub fn build_p2p_session(
 	game_speed: &GameSpeed,
-	players: Vec<NetworkPlayer>,
-) {
+	players: &Vec<NetworkPlayer>,
+	socket: impl NonBlockingSocket<String> + 'static,
+) -> P2PSession<GGRSConfig> {
 	let nbr_player = players.iter().count();
 	let mut sess_build = SessionBuilder::<GGRSConfig>::new()
         .with_num_players(nbr_player)
@@ -64,12 +37,21 @@ pub fn create_session(
 		}
     }
 
-	let sess = sess_build.start_p2p_session(LocalSocket::default()).unwrap();
+	sess_build.start_p2p_session(socket).unwrap()
+}
+
+pub fn create_session(
+	commands: &mut Commands,
+	game_speed: &GameSpeed,
+	players: Vec<NetworkPlayer>,
+	socket: impl NonBlockingSocket<String> + 'static,
+) {
+	let sess = build_p2p_session(game_speed, &players, socket);
 
     commands.insert_resource(sess);
     commands.insert_resource(SessionType::P2PSession);
     commands.insert_resource(LocalHandles {
-        handles: (0..nbr_player).collect(),
+        handles: players.iter().enumerate().filter(|(_, p)| p.address == "localhost").map(|(i, _)| i).collect(),
     });
 
 }
@@ -78,6 +60,8 @@ pub fn system_cleanup_network_session(
 	mut commands: Commands,
 ) {
 	commands.remove_resource::<P2PSession<GGRSConfig>>();
+	commands.remove_resource::<SpectatorRelay>();
+	commands.remove_resource::<SpectatorClient>();
 	commands.remove_resource::<LocalHandles>();
 	commands.remove_resource::<SessionType>();
 
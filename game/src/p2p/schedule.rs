@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy_ggrs::GGRSPlugin;
+use ggrs::PlayerHandle;
+
+use crate::shared::{
+    game::{system_zombie_game, system_end_game, increase_frame_system, ZombieGame, GameSpeed},
+    zombies::zombie::{system_zombie_handle, Zombie, BotDestination, system_move_zombie},
+    player::{input::{apply_input_players, FrameCount, BoxInput, GGRSConfig, update_velocity_player, move_players}, interaction::system_interaction_player, system_health_player, system_respawn_players, Player},
+    weapons::{weapons::{handle_weapon_input, system_ammunition_pickup, system_give_weapon, system_weapon_carry_perk, AmmunitionState, AmmunitionPickup, Projectile, ProjectileHits, WeaponSlot, WeaponSlots, WeaponRecoil, Weapon, ActiveWeapon, GiveWeapon, WeaponCarryPerk}, ammunition::{apply_velocity, movement_projectile, system_expire_effects}, equipment::{system_throw_equipment, system_grenade, system_refill_equipment, Grenade, LethalEquipment}, melee::{system_melee, system_melee_upgrade, MeleeWeapon, MeleeUpgrade}},
+    map::ZombieSpawner, character::{Velocity, LookingAt, Death, CharacterMovementState}, health::Health, collider::ProjectileCollider,
+};
+
+use super::{config::{P2PSystemLabel, ROLLBACK_SYSTEMS, CHECKSUM_UPDATE}, checksum::{checksum_zombie, checksum_zombiegame, checksum_player, checksum_ammunition, checksum_projectile}, spectator::system_record_spectator_inputs};
+
+// Systems executed as part of the advance frame update of GGRS
+pub fn rollback_schedule() -> Schedule {
+    Schedule::default().with_stage(
+        ROLLBACK_SYSTEMS,
+        SystemStage::parallel()
+            .with_system_set(
+                SystemSet::new()
+                    .with_system(system_zombie_handle)
+                    .with_system(apply_input_players)
+                    .with_system(handle_weapon_input)
+                    .with_system(system_throw_equipment)
+                    .with_system(system_interaction_player)
+                    .with_system(system_record_spectator_inputs)
+                    .label(P2PSystemLabel::Input)
+            )
+            .with_system_set(
+                SystemSet::new()
+                    .with_system(update_velocity_player)
+                    .with_system(system_move_zombie)
+                    .with_system(apply_velocity)
+                    .label(P2PSystemLabel::Move)
+                    .after(P2PSystemLabel::Input)
+            )
+            .with_system_set(
+                SystemSet::new()
+                    .with_system(move_players)
+                    .with_system(movement_projectile)
+                    .with_system(system_grenade)
+                    .with_system(system_expire_effects)
+                    .label(P2PSystemLabel::Collision)
+                    .after(P2PSystemLabel::Move)
+            )
+            .with_system_set(
+                SystemSet::new()
+                    .with_system(system_health_player)
+                    .with_system(system_respawn_players.after(P2PSystemLabel::ZombieGame))
+                    .with_system(system_zombie_game.label(P2PSystemLabel::ZombieGame))
+                    .with_system(system_ammunition_pickup)
+                    .with_system(system_refill_equipment.after(P2PSystemLabel::ZombieGame))
+                    .with_system(system_melee)
+                    .with_system(system_melee_upgrade)
+                    .with_system(system_give_weapon)
+                    .with_system(system_weapon_carry_perk)
+                    .after(P2PSystemLabel::Collision)
+                    .label(P2PSystemLabel::GameLogic)
+            )
+            .with_system_set(
+                SystemSet::new()
+                    .with_system(increase_frame_system)
+                    .with_system(system_end_game)
+                    .after(P2PSystemLabel::GameLogic)
+            )
+    )
+    .with_stage_after(
+        ROLLBACK_SYSTEMS,
+        CHECKSUM_UPDATE,
+        SystemStage::parallel()
+                .with_system(checksum_zombie)
+                .with_system(checksum_zombiegame)
+                .with_system(checksum_player)
+                .with_system(checksum_ammunition)
+                .with_system(checksum_projectile)
+    )
+}
+
+// Build the GGRS plugin with the input system that provide the input of
+// the local players and the rollback schedule to execute
+pub fn build_ggrs_plugin<Params>(
+    app: &mut App,
+    game_speed: &GameSpeed,
+    input_system: impl IntoSystem<PlayerHandle, BoxInput, Params>,
+    schedule: Schedule,
+) {
+    GGRSPlugin::<GGRSConfig>::new()
+        // define frequency of rollback game logic update
+        .with_update_frequency(game_speed.1)
+        // define system that returns inputs given a player handle, so GGRS can send the inputs around
+        .with_input_system(input_system)
+        // register types of components AND resources you want to be rolled back
+        .register_rollback_type::<Player>()
+        .register_rollback_type::<LookingAt>()
+        .register_rollback_type::<Transform>()
+        .register_rollback_type::<Velocity>()
+        .register_rollback_type::<FrameCount>()
+        .register_rollback_type::<Weapon>()
+        .register_rollback_type::<AmmunitionState>()
+        .register_rollback_type::<AmmunitionPickup>()
+        .register_rollback_type::<Projectile>()
+        .register_rollback_type::<ProjectileHits>()
+        .register_rollback_type::<Grenade>()
+        .register_rollback_type::<LethalEquipment>()
+        .register_rollback_type::<MeleeWeapon>()
+        .register_rollback_type::<MeleeUpgrade>()
+        .register_rollback_type::<ActiveWeapon>()
+        .register_rollback_type::<WeaponSlot>()
+        .register_rollback_type::<WeaponSlots>()
+        .register_rollback_type::<GiveWeapon>()
+        .register_rollback_type::<WeaponCarryPerk>()
+        .register_rollback_type::<WeaponRecoil>()
+        .register_rollback_type::<Zombie>()
+        .register_rollback_type::<ZombieGame>()
+        .register_rollback_type::<ZombieSpawner>()
+        .register_rollback_type::<CharacterMovementState>()
+        .register_rollback_type::<BotDestination>()
+        .register_rollback_type::<Health>()
+        .register_rollback_type::<ProjectileCollider>()
+        //.register_rollback_type::<HealthRegeneration>()
+        .register_rollback_type::<Death>()
+        .with_rollback_schedule(schedule)
+        // make it happen in the bevy app
+        .build(app);
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use ggrs::{InputStatus, P2PSession};
+use matchbox_socket::WebRtcSocket;
+use serde::{Deserialize, Serialize};
+
+use crate::shared::{game::{GameSpeed, ZombieGame}, player::input::{BoxInput, FrameCount, GGRSConfig}};
+
+use super::schedule::rollback_schedule;
+
+// GGRS can't add a spectator to a running session , the spectators join their own
+// room where the host relay the confirmed inputs of the players and they replay them
+pub const SPECTATOR_ROOM_SUFFIX: &str = "_spectators";
+
+// maximum number of frames sent in one message to a spectator
+const SPECTATOR_FRAMES_PER_MESSAGE: usize = 60;
+// frames behind the host before the spectator simulate faster to catch up
+const SPECTATOR_MAX_DELAY: usize = 120;
+// frames simulated by update when catching up , a late spectator replay the match from the start
+const SPECTATOR_CATCH_UP_FRAMES: usize = 20;
+// frames kept by the relay for the spectators joining late , 30 minutes at 60 fps
+const SPECTATOR_MAX_RECORDED_FRAMES: usize = 60 * 60 * 30;
+
+#[derive(Serialize, Deserialize)]
+enum SpectatorMessage {
+	// inputs of every player for the frames from `start`
+	Frames { start: u32, inputs: Vec<Vec<BoxInput>> },
+	// next frame expected by the spectator
+	Ack { next: u32 },
+}
+
+// Relay of the inputs of the match to the spectators , only owned by the host (player 0).
+// A spectator replay the match from the first frame so the relay keep the inputs of every
+// frame , up to `SPECTATOR_MAX_RECORDED_FRAMES`. Over it the frames received by every
+// spectator are dropped and the spectators joining after can't watch the match
+pub struct SpectatorRelay {
+	socket: WebRtcSocket,
+	// frame count of the first frame of the match
+	first_frame: Option<u32>,
+	// frames of the match dropped from the start of `inputs`
+	dropped: usize,
+	// inputs of the players for the frames of the match kept , overwritten on rollback
+	inputs: Vec<Vec<BoxInput>>,
+	// next frame needed by each spectator
+	spectators: HashMap<String, u32>,
+}
+
+impl SpectatorRelay {
+	pub fn new(socket: WebRtcSocket) -> Self {
+		Self {
+			socket,
+			first_frame: None,
+			dropped: 0,
+			inputs: vec![],
+			spectators: HashMap::new(),
+		}
+	}
+}
+
+// Replay of the match received from the host
+pub struct SpectatorClient {
+	socket: WebRtcSocket,
+	// inputs of the players for the frames received , in order
+	inputs: Vec<Vec<BoxInput>>,
+	// next frame to simulate
+	next_frame: usize,
+	accumulator: f32,
+	schedule: Schedule,
+}
+
+impl SpectatorClient {
+	pub fn new(socket: WebRtcSocket) -> Self {
+		Self {
+			socket,
+			inputs: vec![],
+			next_frame: 0,
+			accumulator: 0.,
+			schedule: rollback_schedule(),
+		}
+	}
+
+	// true when the host has started to send the match
+	pub fn is_ready(&self) -> bool {
+		!self.inputs.is_empty()
+	}
+
+	pub fn receive_frames(&mut self) {
+		self.socket.accept_new_connections();
+
+		for (peer, packet) in self.socket.receive() {
+			let (start, inputs) = match bincode::deserialize(&packet) {
+				Ok(SpectatorMessage::Frames { start, inputs }) => (start as usize, inputs),
+				_ => continue,
+			};
+			// messages can be lost or out of order , only keep the frames following the ones received
+			if start <= self.inputs.len() {
+				let skip = self.inputs.len() - start;
+				self.inputs.extend(inputs.into_iter().skip(skip));
+			}
+
+			match bincode::serialize(&SpectatorMessage::Ack { next: self.inputs.len() as u32 }) {
+				Ok(ack) => self.socket.send(ack.into_boxed_slice(), peer),
+				Err(err) => println!("Failed to serialize spectator ack : {}", err),
+			}
+		}
+	}
+}
+
+// Keep the inputs of each frame simulated by the host , the last simulation
+// of a frame is the right one once the frame is confirmed
+pub fn system_record_spectator_inputs(
+	relay: Option<ResMut<SpectatorRelay>>,
+	frame_count: Res<FrameCount>,
+	inputs: Res<Vec<(BoxInput, InputStatus)>>,
+) {
+	let mut relay = match relay {
+		Some(relay) => relay,
+		None => return,
+	};
+
+	let first_frame = *relay.first_frame.get_or_insert(frame_count.frame);
+	let frame = frame_count.frame.saturating_sub(first_frame) as usize;
+	// only the confirmed frames are dropped , they are never simulated again
+	if frame < relay.dropped {
+		return;
+	}
+	let index = frame - relay.dropped;
+
+	let frame_inputs: Vec<BoxInput> = inputs.iter().map(|(input, status)| match status {
+		InputStatus::Disconnected => BoxInput::default(),
+		_ => *input,
+	}).collect();
+
+	if index < relay.inputs.len() {
+		relay.inputs[index] = frame_inputs;
+	} else {
+		// every frame is simulated , a gap is a bug. The spectators replay it with the players doing nothing
+		if index > relay.inputs.len() {
+			println!("Spectator relay missing the frames {} to {}", relay.dropped + relay.inputs.len(), frame - 1);
+			let players = frame_inputs.len();
+			relay.inputs.resize(index, vec![BoxInput::default(); players]);
+		}
+		relay.inputs.push(frame_inputs);
+	}
+}
+
+// Send the confirmed frames to every spectator connected to the host , a spectator
+// that join late receive the match from the first frame
+pub fn system_spectator_relay(
+	relay: Option<ResMut<SpectatorRelay>>,
+	session: Option<Res<P2PSession<GGRSConfig>>>,
+) {
+	let (mut relay, session) = match (relay, session) {
+		(Some(relay), Some(session)) => (relay, session),
+		_ => return,
+	};
+
+	for peer in relay.socket.accept_new_connections() {
+		if relay.dropped > 0 {
+			println!("Spectator {} joined too late , the start of the match is not kept", peer);
+			continue;
+		}
+		relay.spectators.insert(peer, 0);
+	}
+
+	for (peer, packet) in relay.socket.receive() {
+		if let Ok(SpectatorMessage::Ack { next }) = bincode::deserialize(&packet) {
+			if let Some(progress) = relay.spectators.get_mut(&peer) {
+				*progress = (*progress).max(next);
+			}
+		}
+	}
+
+	let confirmed_frame = session.confirmed_frame();
+	if confirmed_frame < 0 {
+		return;
+	}
+	// frames from the start of the match
+	let available = (relay.dropped + relay.inputs.len()).min(confirmed_frame as usize + 1);
+
+	// over the limit drop the frames confirmed and received by every spectator
+	if relay.inputs.len() > SPECTATOR_MAX_RECORDED_FRAMES {
+		let received = relay.spectators.values().map(|x| *x as usize).min().unwrap_or(available).min(available);
+		let drop = received.saturating_sub(relay.dropped).min(relay.inputs.len() - SPECTATOR_MAX_RECORDED_FRAMES);
+		relay.inputs.drain(..drop);
+		relay.dropped += drop;
+	}
+
+	let mut messages = vec![];
+	for (peer, next) in relay.spectators.iter() {
+		let start = *next as usize;
+		if start >= available {
+			continue;
+		}
+		let end = available.min(start + SPECTATOR_FRAMES_PER_MESSAGE);
+		let frames = relay.inputs[start - relay.dropped..end - relay.dropped].to_vec();
+		let message = SpectatorMessage::Frames { start: start as u32, inputs: frames };
+		match bincode::serialize(&message) {
+			Ok(packet) => messages.push((peer.clone(), packet)),
+			Err(err) => println!("Failed to serialize spectator frames : {}", err),
+		}
+	}
+
+	for (peer, packet) in messages {
+		relay.socket.send(packet.into_boxed_slice(), peer);
+	}
+}
+
+// Simulate the frames received from the host with the rollback schedule , the
+// spectator stay a little behind the players and catch up when too late
+pub fn system_spectator_advance(world: &mut World) {
+	let mut client = match world.remove_resource::<SpectatorClient>() {
+		Some(client) => client,
+		None => return,
+	};
+
+	client.receive_frames();
+
+	// wait for the level to be loaded before replaying the match
+	let loaded = world.query::<&ZombieGame>().iter(world).next().is_some();
+	if loaded {
+		let frame_duration = world.resource::<GameSpeed>().0;
+		client.accumulator += world.resource::<Time>().delta_seconds();
+
+		let behind = client.inputs.len() - client.next_frame;
+		let mut frames = (client.accumulator / frame_duration) as usize;
+		if behind > SPECTATOR_MAX_DELAY {
+			frames = frames.max(SPECTATOR_CATCH_UP_FRAMES);
+		}
+		let frames = frames.min(behind);
+
+		// don't accumulate the time spent waiting for the host
+		client.accumulator = (client.accumulator - frames as f32 * frame_duration).clamp(0., frame_duration);
+
+		for _ in 0..frames {
+			let inputs: Vec<(BoxInput, InputStatus)> = client.inputs[client.next_frame].iter()
+				.map(|input| (*input, InputStatus::Confirmed))
+				.collect();
+			world.insert_resource(inputs);
+			client.schedule.run(world);
+			client.next_frame += 1;
+		}
+	}
+
+	world.insert_resource(client);
+}
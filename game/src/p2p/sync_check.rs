@@ -0,0 +1,180 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use bevy::{prelude::*, asset::AssetPlugin, hierarchy::HierarchyPlugin, transform::TransformPlugin};
+use bevy_ggrs::{Rollback, SessionType};
+use ggrs::{P2PSession, PlayerHandle};
+
+use crate::shared::{
+	game::{GameSpeed, GameState, LevelMapRequested, DEFAULT_MAP, DEFAULT_LEVEL, ZombieGameConfig, ZombieGamePlugin, ZombiePlayerInformation, setup_zombie_game},
+	map::MapDataPlugin,
+	player::input::{BoxInput, FrameCount, GGRSConfig, PlayerCurrentInput, SupportedController, INPUT_UP, INPUT_DOWN, INPUT_LEFT, INPUT_RIGHT, INPUT_FIRE, INPUT_JUST_FIRE},
+	utils::{Checksum, fletcher16},
+};
+
+use super::{
+	config::{CHECKSUM_UPDATE, LocalHandles},
+	loopback::LoopbackNetwork,
+	online::{NetworkPlayer, build_p2p_session},
+	schedule::{build_ggrs_plugin, rollback_schedule},
+};
+
+const CHECKSUM_HISTORY: &str = "checksum_history";
+const SYNC_CHECK_PLAYERS: usize = 2;
+const SYNC_CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Checksum of the world for each frame simulated , a frame simulated again
+// after a rollback override the previous value
+#[derive(Default)]
+pub struct ChecksumHistory(pub HashMap<u32, u16>);
+
+// Read the number of frames to validate from `--sync-check <frames>`
+pub fn sync_check_frames_from_args() -> Option<u32> {
+	let args: Vec<String> = std::env::args().collect();
+	let index = args.iter().position(|x| x == "--sync-check")?;
+	Some(args.get(index + 1).and_then(|x| x.parse::<u32>().ok()).unwrap_or(600))
+}
+
+// Input of a player only depends on the handle and the frame , each player
+// walk in a different square and fire at a different rate
+fn scripted_box_input(handle: PlayerHandle, frame: u32) -> BoxInput {
+	let directions = [INPUT_UP, INPUT_RIGHT, INPUT_DOWN, INPUT_LEFT];
+	let mut input = directions[((frame / 60) as usize + handle) % directions.len()];
+
+	if frame % (20 + (handle as u32) * 10) == 0 {
+		input |= INPUT_FIRE | INPUT_JUST_FIRE;
+	}
+
+	let angle = (frame as f32 / 30.) + (handle as f32);
+	BoxInput { inp: input, right_x: (angle.cos() * 300.) as i32, right_y: (angle.sin() * 300.) as i32 }
+}
+
+fn scripted_input(handle: In<PlayerHandle>, frame_count: Res<FrameCount>) -> BoxInput {
+	scripted_box_input(handle.0, frame_count.frame)
+}
+
+fn record_checksum_history(
+	frame_count: Res<FrameCount>,
+	mut history: ResMut<ChecksumHistory>,
+	query: Query<(&Rollback, &Checksum)>,
+) {
+	let mut checksums: Vec<(u32, u16)> = query.iter().map(|(rollback, checksum)| (rollback.id(), checksum.value)).collect();
+	checksums.sort();
+
+	let mut bytes = Vec::with_capacity(checksums.len() * 6);
+	for (id, value) in checksums.iter() {
+		bytes.extend_from_slice(&id.to_le_bytes());
+		bytes.extend_from_slice(&value.to_le_bytes());
+	}
+
+	history.0.insert(frame_count.frame, fletcher16(&bytes));
+}
+
+// Create a headless world running the rollback schedule for the local player `local_handle`
+fn build_sync_check_app(session: P2PSession<GGRSConfig>, local_handle: usize) -> App {
+	let mut app = App::new();
+	let game_speed = GameSpeed::default();
+
+	let schedule = rollback_schedule().with_stage_after(
+		CHECKSUM_UPDATE,
+		CHECKSUM_HISTORY,
+		SystemStage::single(record_checksum_history),
+	);
+
+	build_ggrs_plugin(&mut app, &game_speed, scripted_input, schedule);
+
+	app.insert_resource(LevelMapRequested{map: DEFAULT_MAP.to_string(), level: DEFAULT_LEVEL.to_string()})
+		.insert_resource(FrameCount { frame: 0 })
+		.init_resource::<ChecksumHistory>()
+		.add_plugins(MinimalPlugins)
+		.add_plugin(AssetPlugin)
+		.add_plugin(TransformPlugin)
+		.add_plugin(HierarchyPlugin)
+		.add_plugin(MapDataPlugin {})
+		.add_plugin(ZombieGamePlugin {})
+		.add_system_set(
+			SystemSet::on_enter(GameState::PlayingZombie)
+				.with_system(setup_zombie_game)
+		);
+
+	app.insert_resource(session)
+		.insert_resource(SessionType::P2PSession)
+		.insert_resource(LocalHandles { handles: vec![local_handle] });
+
+	{
+		let mut zombie_game = app.world.resource_mut::<ZombieGameConfig>();
+		zombie_game.players = (0..SYNC_CHECK_PLAYERS).map(|i| ZombiePlayerInformation {
+			name: format!("Player {}", i + 1),
+			controller: PlayerCurrentInput { input_source: SupportedController::Keyboard, gamepad: None, ..default() },
+			index: i,
+			is_local: i == local_handle,
+		}).collect();
+	}
+
+	app.world.resource_mut::<State<GameState>>().set(GameState::PlayingZombie).unwrap();
+
+	app
+}
+
+// Run two simulations connected by a loopback network with scripted inputs
+// and validate that both produce the same checksum for each confirmed frame
+pub fn run_sync_check(frames: u32) -> Result<(), String> {
+	let game_speed = GameSpeed::default();
+	let network = LoopbackNetwork::default();
+	let addresses: Vec<String> = (0..SYNC_CHECK_PLAYERS).map(|i| format!("loopback-{}", i)).collect();
+
+	let mut apps: Vec<App> = (0..SYNC_CHECK_PLAYERS).map(|local_handle| {
+		let players: Vec<NetworkPlayer> = addresses.iter().enumerate().map(|(i, address)| NetworkPlayer {
+			address: if i == local_handle { "localhost".to_string() } else { address.clone() },
+		}).collect();
+		let session = build_p2p_session(&game_speed, &players, network.socket(addresses[local_handle].as_str()));
+		build_sync_check_app(session, local_handle)
+	}).collect();
+
+	let started_at = Instant::now();
+	loop {
+		for app in apps.iter_mut() {
+			app.update();
+		}
+
+		let confirmed_frame = apps.iter()
+			.map(|app| app.world.resource::<P2PSession<GGRSConfig>>().confirmed_frame())
+			.min()
+			.unwrap_or(0);
+		if confirmed_frame >= frames as i32 {
+			break;
+		}
+
+		if started_at.elapsed() > SYNC_CHECK_TIMEOUT {
+			return Err(format!("Timeout , only {} frames confirmed", confirmed_frame));
+		}
+
+		std::thread::sleep(Duration::from_secs_f32(game_speed.0));
+	}
+
+	let reference = &apps[0].world.resource::<ChecksumHistory>().0;
+	for (i, app) in apps.iter().enumerate().skip(1) {
+		let history = &app.world.resource::<ChecksumHistory>().0;
+		for frame in 1..=frames {
+			let (expected, actual) = (reference.get(&frame), history.get(&frame));
+			if expected != actual {
+				return Err(format!("Desync at frame {} between player 0 and player {} : {:?} != {:?}", frame, i, expected, actual));
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::run_sync_check;
+
+	// players , ammunition , projectiles , zombies and the game must match on both sides.
+	// GGRS advance the frames with the wall clock so the check take a few seconds ,
+	// run it with `cargo make test-sync-check`
+	#[test]
+	#[ignore]
+	fn two_players_stay_in_sync() {
+		assert_eq!(run_sync_check(300), Ok(()));
+	}
+}
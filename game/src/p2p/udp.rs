@@ -0,0 +1,77 @@
+use std::{io::ErrorKind, net::{SocketAddr, ToSocketAddrs, UdpSocket}};
+
+use ggrs::{Message, NonBlockingSocket};
+
+const RECV_BUFFER_SIZE: usize = 4096;
+
+// UDP socket addressed by "ip:port" string , used to play on LAN or on the
+// same computer without a signaling server
+pub struct LanSocket {
+	socket: UdpSocket,
+	buffer: [u8; RECV_BUFFER_SIZE],
+}
+
+impl LanSocket {
+	pub fn bind_to_port(port: u16) -> Result<Self, std::io::Error> {
+		let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], port)))?;
+		socket.set_nonblocking(true)?;
+		Ok(Self {
+			socket,
+			buffer: [0; RECV_BUFFER_SIZE],
+		})
+	}
+}
+
+// resolve "host:port" to the ip used by the socket , the host can be an ip or a name like
+// "localhost". The address is normalized so it match the one received from the socket
+pub fn parse_address(address: &str) -> Option<String> {
+	address.trim().to_socket_addrs().ok()?
+		// the socket is bound on an ipv4 address
+		.find(|x| x.is_ipv4())
+		.map(|x| x.to_string())
+}
+
+impl NonBlockingSocket<String> for LanSocket {
+
+	fn send_to(&mut self, msg: &Message, addr: &String) {
+		let addr: SocketAddr = match addr.parse() {
+			Ok(addr) => addr,
+			Err(_) => {
+				println!("Invalid peer address {}", addr);
+				return;
+			}
+		};
+		let buf = match bincode::serialize(&msg) {
+			Ok(buf) => buf,
+			Err(err) => {
+				println!("Failed to serialize message for {} : {}", addr, err);
+				return;
+			}
+		};
+		// a lost packet is handled by GGRS , the message is dropped
+		if let Err(err) = self.socket.send_to(&buf, addr) {
+			println!("Failed to send message to {} : {}", addr, err);
+		}
+	}
+
+	fn receive_all_messages(&mut self) -> Vec<(String, Message)> {
+		let mut received_messages = Vec::new();
+		loop {
+			match self.socket.recv_from(&mut self.buffer) {
+				Ok((number_of_bytes, src_addr)) => {
+					if let Ok(msg) = bincode::deserialize(&self.buffer[0..number_of_bytes]) {
+						received_messages.push((src_addr.to_string(), msg));
+					}
+				}
+				// there are no more messages
+				Err(ref err) if err.kind() == ErrorKind::WouldBlock => return received_messages,
+				// datagram socket sometimes get this error as a result of calling the send_to method
+				Err(ref err) if err.kind() == ErrorKind::ConnectionReset => continue,
+				Err(err) => {
+					println!("Failed to receive message : {:?} {}", err.kind(), err);
+					return received_messages;
+				}
+			}
+		}
+	}
+}
@@ -16,6 +16,34 @@ pub struct ProjectileCollider {}
 #[derive(Component)]
 pub struct CollisionEvent {}
 
+// Distance along the ray to the first intersection with the box of `center` and `size` ,
+// `direction` need to be normalized
+pub fn ray_aabb(origin: Vec2, direction: Vec2, center: Vec2, size: Vec2) -> Option<f32> {
+    let min = center - size / 2.;
+    let max = center + size / 2.;
+
+    let mut t_min = f32::MIN;
+    let mut t_max = f32::MAX;
+    for (o, d, min, max) in [(origin.x, direction.x, min.x, max.x), (origin.y, direction.y, min.y, max.y)] {
+        if d.abs() < f32::EPSILON {
+            // parallel to the slab , the origin need to be inside it
+            if o < min || o > max {
+                return None;
+            }
+        } else {
+            let t1 = (min - o) / d;
+            let t2 = (max - o) / d;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+        }
+    }
+
+    if t_max < t_min.max(0.) {
+        return None;
+    }
+    Some(t_min.max(0.))
+}
+
 
 pub fn is_colliding<T : Component, R : Component>(
     destination: Vec3,
@@ -34,4 +62,137 @@ pub fn is_colliding<T : Component, R : Component>(
         }
     }
     return false;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bevy::prelude::*;
+    use bevy_ggrs::Rollback;
+
+    use crate::shared::{
+        health::Health, map::MapElementPosition, zombies::zombie::Zombie,
+        weapons::{ammunition::{hit_along_segment, HitscanColliderQuery}, weapons::{Projectile, ProjectileHits}},
+    };
+
+    use super::{ray_aabb, ProjectileCollider};
+
+    const BOX_SIZE: Vec2 = bevy::math::const_vec2!([20., 20.]);
+
+    #[test]
+    fn ray_hit_box_from_outside() {
+        assert_eq!(ray_aabb(Vec2::ZERO, Vec2::X, Vec2::new(100., 0.), BOX_SIZE), Some(90.));
+        let distance = ray_aabb(Vec2::ZERO, Vec2::ONE.normalize(), Vec2::new(100., 100.), BOX_SIZE).unwrap();
+        assert!((distance - 90. * 2f32.sqrt()).abs() < 0.001);
+    }
+
+    #[test]
+    fn ray_miss_box() {
+        assert_eq!(ray_aabb(Vec2::ZERO, Vec2::X, Vec2::new(100., 50.), BOX_SIZE), None);
+        assert_eq!(ray_aabb(Vec2::ZERO, Vec2::Y, Vec2::new(100., 0.), BOX_SIZE), None);
+    }
+
+    #[test]
+    fn ray_from_inside_box_hit_at_origin() {
+        assert_eq!(ray_aabb(Vec2::new(95., 5.), Vec2::X, Vec2::new(100., 0.), BOX_SIZE), Some(0.));
+    }
+
+    #[test]
+    fn ray_parallel_to_box() {
+        // along the slab of the box
+        assert_eq!(ray_aabb(Vec2::new(0., 5.), Vec2::X, Vec2::new(100., 0.), BOX_SIZE), Some(90.));
+        // outside the slab of the box
+        assert_eq!(ray_aabb(Vec2::new(0., 15.), Vec2::X, Vec2::new(100., 0.), BOX_SIZE), None);
+    }
+
+    #[test]
+    fn ray_ignore_box_behind_origin() {
+        assert_eq!(ray_aabb(Vec2::ZERO, Vec2::X, Vec2::new(-100., 0.), BOX_SIZE), None);
+    }
+
+    // Result of `fire_segment` , the projectile fired along the x axis from the origin
+    #[derive(Default)]
+    struct SegmentResult {
+        stopped: Option<f32>,
+        projectile: Projectile,
+        hits: Vec<u32>,
+    }
+
+    fn fire_segment(
+        mut commands: Commands,
+        mut collider_query: HitscanColliderQuery,
+        mut result: ResMut<SegmentResult>,
+    ) {
+        let mut projectile = Projectile { damage: 4., penetration: 2, damage_loss: 0.5 };
+        let mut projectile_hits = ProjectileHits::default();
+        result.stopped = hit_along_segment(
+            &mut commands,
+            &mut collider_query,
+            Vec2::ZERO,
+            Vec2::X,
+            1000.,
+            Vec2::ZERO,
+            &mut projectile,
+            &mut projectile_hits,
+            &mut HashSet::new(),
+        );
+        result.projectile = projectile;
+        result.hits = projectile_hits.hits;
+    }
+
+    fn spawn_collider(world: &mut World, x: f32, id: u32, zombie: bool) -> Entity {
+        let mut entity = world.spawn();
+        entity
+            .insert(Transform::from_xyz(x, 0., 0.))
+            .insert(MapElementPosition { position: Vec2::new(x, 0.), size: BOX_SIZE, rotation: 0 })
+            .insert(ProjectileCollider {})
+            .insert(Rollback::new(id));
+        if zombie {
+            entity
+                .insert(Zombie::default())
+                .insert(Health { current_health: 10., tmp_health: 10., max_health: 10. });
+        }
+        entity.id()
+    }
+
+    fn run_fire_segment(world: &mut World) -> SegmentResult {
+        world.insert_resource(SegmentResult::default());
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(fire_segment);
+        stage.run(world);
+        world.remove_resource::<SegmentResult>().unwrap()
+    }
+
+    #[test]
+    fn segment_hit_closest_zombies_up_to_penetration() {
+        let mut world = World::new();
+        // spawned from the farthest so the hits need to be sorted
+        let far = spawn_collider(&mut world, 300., 3, true);
+        let middle = spawn_collider(&mut world, 200., 2, true);
+        let close = spawn_collider(&mut world, 100., 1, true);
+
+        let result = run_fire_segment(&mut world);
+
+        assert_eq!(result.stopped, Some(190.));
+        assert_eq!(result.hits, vec![1, 2]);
+        assert_eq!(result.projectile.penetration, 0);
+        assert_eq!(world.get::<Health>(close).unwrap().current_health, 6.);
+        assert_eq!(world.get::<Health>(middle).unwrap().current_health, 8.);
+        assert_eq!(world.get::<Health>(far).unwrap().current_health, 10.);
+    }
+
+    #[test]
+    fn segment_stopped_by_wall() {
+        let mut world = World::new();
+        let zombie = spawn_collider(&mut world, 200., 2, true);
+        spawn_collider(&mut world, 100., 1, false);
+
+        let result = run_fire_segment(&mut world);
+
+        assert_eq!(result.stopped, Some(90.));
+        assert!(result.hits.is_empty());
+        assert_eq!(result.projectile.penetration, 2);
+        assert_eq!(world.get::<Health>(zombie).unwrap().current_health, 10.);
+    }
+}
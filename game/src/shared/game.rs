@@ -1,29 +1,29 @@
-use std::time::Duration;
-
 use crate::shared::health::Health;
 use crate::shared::map::{Window, WindowPanelBundle};
-use crate::shared::player::PlayerDeadEvent;
+use crate::shared::player::{PlayerDeadEvent, CameraShakeEvent};
 use crate::shared::player::input::{AvailableGameController, PlayerCurrentInput, FrameCount, BoxInput};
 use crate::shared::player::{
     setup_player,
-    interaction::PlayerInteraction
+    interaction::PlayerInteraction,
+    spawn::{sorted_spawns, select_player_spawn},
 };
 use crate::shared::utils::Checksum;
 use crate::shared::weapons::loader::{WeaponAssetPlugin, WeaponAssetState};
-use crate::shared::weapons::weapons::Weapon;
+use crate::shared::weapons::weapons::{Weapon, AmmunitionState};
+use crate::shared::character::Death;
 
-use super::map::{MapElementPosition,  ZombieSpawner, render::MapDataState};
+use super::map::{MapElementPosition,  ZombieSpawner, PlayerSpawn, render::MapDataState};
 use super::player::Player;
 use super::zombies::spawner::*;
 use super::zombies::zombie::*;
 
 
 use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::reflect::TypeUuid;
 use bevy_ggrs::{RollbackIdProvider, Rollback};
 use ggrs::InputStatus;
-use rand::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use pathfinding::prelude::astar;
@@ -44,6 +44,8 @@ impl Default for GameSpeed {
 pub enum GameState {
     Menu,
     OnlineMenu,
+    LanMenu,
+    MapEditor,
     PlayingZombie,
     GameOver,
 }
@@ -61,17 +63,80 @@ pub enum ZombieGameState {
 
 
 
+fn default_starting_countdown() -> f32 {
+    3.
+}
+
+fn default_round_interlude() -> f32 {
+    5.
+}
+
+fn default_spawn_per_tick() -> usize {
+    1
+}
+
 #[derive(Default, Deserialize, Clone, Debug, Reflect)]
 pub struct MapRoundConfiguration {
     pub starting_zombie: i32,
     pub round_increments: i32,
     pub initial_timeout: u64,
+    // seconds before the first round
+    #[serde(default = "default_starting_countdown")]
+    pub starting_countdown: f32,
+    // seconds between two rounds
+    #[serde(default = "default_round_interlude")]
+    pub round_interlude: f32,
+    #[serde(default)]
+    pub spawner_selection: SpawnerSelection,
+    // zombies spawned each time the spawn timer finish , not used by the `All` selection
+    #[serde(default = "default_spawn_per_tick")]
+    pub spawn_per_tick: usize,
+    // only the spawners without zone or in the zone of a player can spawn
+    #[serde(default)]
+    pub active_zones_only: bool,
+    // points of the players at the start of the game
+    #[serde(default)]
+    pub starting_points: u32,
+    // points earned for each zombie of a round , given at the end of the round
+    #[serde(default = "default_points_per_zombie")]
+    pub points_per_zombie: u32,
+}
+
+fn default_points_per_zombie() -> u32 {
+    10
+}
+
+fn default_starting_equipment() -> Option<String> {
+    Some("frag".to_string())
+}
+
+fn default_starting_melee() -> String {
+    "fists".to_string()
+}
+
+fn default_weapon_carry_limit() -> usize {
+    2
+}
+
+fn default_weapon_carry_limit_perk() -> usize {
+    3
 }
 
 #[derive(Default, Deserialize, Clone, Debug, Reflect)]
 pub struct StartingWeapons {
     pub starting_weapon: String,
     pub starting_alternate_weapon: Option<String>,
+    // name of the lethal equipment in `weapons/default.equipment.ron`
+    #[serde(default = "default_starting_equipment")]
+    pub starting_equipment: Option<String>,
+    // name of the melee weapon in the `melees` of `weapons/default.equipment.ron`
+    #[serde(default = "default_starting_melee")]
+    pub starting_melee: String,
+    // weapons a player can carry , and with the perk
+    #[serde(default = "default_weapon_carry_limit")]
+    pub weapon_carry_limit: usize,
+    #[serde(default = "default_weapon_carry_limit_perk")]
+    pub weapon_carry_limit_perk: usize,
 }
 
 #[derive(Default, Deserialize, Clone, Debug, Reflect)]
@@ -83,12 +148,206 @@ pub struct WindowPanelConfiguration {
 }
 
 
+// Modifiers of the rules of the game for the difficulty of a level ,
+// the default values are the rules of the easy level
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DifficultyModifiers {
+    // health of the zombies on the first round , increase each round up to the max
+    pub zombie_health: f32,
+    pub zombie_health_increment: f32,
+    pub zombie_max_health: f32,
+    // steps taken each frame by the zombies on the first round , increase each round up to the max
+    pub zombie_speed: f32,
+    pub zombie_speed_increment: f32,
+    pub zombie_max_speed: f32,
+    // health removed from a player or a window panel by each attack of a zombie
+    pub zombie_damage: f32,
+    // zombies alive at the same time
+    pub max_concurrent_zombie: i32,
+
+    pub player_max_health: f32,
+    pub player_regeneration_timeout: f32,
+    pub player_regeneration_amount: f32,
+
+    // multiply the starting ammunition of the weapons
+    pub ammunition_multiplier: f32,
+}
+
+impl Default for DifficultyModifiers {
+    fn default() -> Self {
+        DifficultyModifiers {
+            zombie_health: 1.,
+            zombie_health_increment: 0.,
+            zombie_max_health: 1.,
+            zombie_speed: 1.,
+            zombie_speed_increment: 0.,
+            zombie_max_speed: 1.,
+            zombie_damage: 1.,
+            max_concurrent_zombie: 20,
+            player_max_health: 3.,
+            player_regeneration_timeout: 2.,
+            player_regeneration_amount: 1.,
+            ammunition_multiplier: 1.,
+        }
+    }
+}
+
+impl DifficultyModifiers {
+    pub fn zombie_health(&self, round: i32) -> f32 {
+        (self.zombie_health + self.zombie_health_increment * (round - 1) as f32).min(self.zombie_max_health.max(self.zombie_health))
+    }
+
+    pub fn zombie_speed(&self, round: i32) -> f32 {
+        (self.zombie_speed + self.zombie_speed_increment * (round - 1) as f32).min(self.zombie_max_speed.max(self.zombie_speed))
+    }
+}
+
+fn default_clamp_min() -> f32 {
+    f32::MIN
+}
+
+fn default_clamp_max() -> f32 {
+    f32::MAX
+}
+
+// Value of a stat of the game for a round , the round start at 1
+#[derive(Deserialize, Clone, Debug)]
+pub enum ScalingCurve {
+    Constant(f32),
+    // start + increment * (round - 1)
+    Linear { start: f32, increment: f32 },
+    // start * factor ^ (round - 1)
+    Exponential { start: f32, factor: f32 },
+    // sum of coefficients[i] * round ^ i , like the zombie count of cod
+    Polynomial { coefficients: Vec<f32> },
+    // (round , value) points , linear between two points and the value of the closest point outside of them
+    Table(Vec<(i32, f32)>),
+    // (round , curve) each curve is used from its round , with the round counted from it
+    Piecewise(Vec<(i32, ScalingCurve)>),
+    Clamp {
+        curve: Box<ScalingCurve>,
+        #[serde(default = "default_clamp_min")]
+        min: f32,
+        #[serde(default = "default_clamp_max")]
+        max: f32,
+    },
+}
+
+impl ScalingCurve {
+    pub fn evaluate(&self, round: i32) -> f32 {
+        match self {
+            ScalingCurve::Constant(value) => *value,
+            ScalingCurve::Linear { start, increment } => start + increment * (round - 1) as f32,
+            ScalingCurve::Exponential { start, factor } => start * factor.powi(round - 1),
+            ScalingCurve::Polynomial { coefficients } => coefficients.iter().enumerate()
+                .map(|(i, c)| c * (round as f32).powi(i as i32))
+                .sum(),
+            ScalingCurve::Table(points) => {
+                let next = points.iter().position(|x| x.0 >= round);
+                match next {
+                    None => points.last().map(|x| x.1).unwrap_or(0.),
+                    Some(0) => points[0].1,
+                    Some(i) => {
+                        let (r1, v1) = points[i - 1];
+                        let (r2, v2) = points[i];
+                        // a table not sorted or with the same round twice has nothing to interpolate
+                        if r2 <= r1 {
+                            return v2;
+                        }
+                        v1 + (v2 - v1) * (round - r1) as f32 / (r2 - r1) as f32
+                    },
+                }
+            },
+            ScalingCurve::Piecewise(curves) => {
+                match curves.iter().rev().find(|x| x.0 <= round).or(curves.first()) {
+                    // before the first curve its first round is used , not extrapolated backward
+                    Some((from, curve)) => curve.evaluate((round - from + 1).max(1)),
+                    None => 0.,
+                }
+            },
+            ScalingCurve::Clamp { curve, min, max } => curve.evaluate(round).max(*min).min(*max),
+        }
+    }
+}
+
+// Curves of the stats of the rounds , the stats without a curve use the linear
+// values of the configuration and the difficulty of the level
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RoundScaling {
+    pub zombie_count: Option<ScalingCurve>,
+    // milliseconds between two spawns of zombies
+    pub spawn_interval: Option<ScalingCurve>,
+    pub max_concurrent_zombie: Option<ScalingCurve>,
+    pub zombie_health: Option<ScalingCurve>,
+    pub zombie_speed: Option<ScalingCurve>,
+}
+
+// A round where an other kind of enemy spawn instead of the zombies ,
+// the players get all their ammunition back at the end
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpecialRoundConfiguration {
+    pub kind: ZombieKind,
+    // first round where it happen
+    pub first_round: i32,
+    // rounds between two special rounds of this kind , 0 to only happen once
+    #[serde(default)]
+    pub interval: i32,
+    // up to this number of rounds randomly added to the interval
+    #[serde(default)]
+    pub random_interval: i32,
+    pub enemy_count: i32,
+    // enemies added for each player after the first one
+    #[serde(default)]
+    pub enemy_per_player: i32,
+    pub health: f32,
+    pub speed: f32,
+    pub damage: f32,
+}
+
+impl SpecialRoundConfiguration {
+    // The random part of the interval is computed from the round number
+    // so every peer schedule the same rounds
+    pub fn is_scheduled(&self, round: i32) -> bool {
+        let mut special_round = self.first_round;
+        while special_round < round && self.interval > 0 {
+            let random = if self.random_interval > 0 {
+                ((special_round as u32).wrapping_mul(2654435761) >> 16) as i32 % (self.random_interval + 1)
+            } else { 0 };
+            special_round += self.interval + random;
+        }
+        special_round == round
+    }
+
+    pub fn enemy_count(&self, nbr_player: usize) -> i32 {
+        self.enemy_count + self.enemy_per_player * (nbr_player.max(1) as i32 - 1)
+    }
+}
+
+// Information display in the menu to select the map and the level
+#[derive(Default, Deserialize, Serialize, Clone, Debug)]
+pub struct AssetMetadata {
+    pub name: String,
+    pub description: String,
+    // path of an image in the assets folder
+    pub preview: Option<String>,
+}
+
 #[derive(Deserialize, TypeUuid, Clone, Component)]
 #[uuid = "39cadc56-aa9c-4543-8640-a018b74b5023"]
 pub struct ZombieLevelAsset {
+    #[serde(default)]
+    pub metadata: AssetMetadata,
     pub configuration: MapRoundConfiguration,
     pub starting_weapons: StartingWeapons,
     pub window_panel: WindowPanelConfiguration,
+    #[serde(default)]
+    pub difficulty: DifficultyModifiers,
+    #[serde(default)]
+    pub special_rounds: Vec<SpecialRoundConfiguration>,
+    #[serde(default)]
+    pub scaling: RoundScaling,
 }
 
 
@@ -119,6 +378,94 @@ pub struct ZombieGame {
     pub round: i32,
     pub state: ZombieGameState,
     pub current_round: CurrentRoundInfo,
+    // frames remaining before the end of the countdown of the starting and interlude state
+    pub countdown: u32,
+    // frames remaining before the next spawn of zombies of the round
+    pub spawn_countdown: u32,
+    // index in the special rounds of the configuration of the current round
+    pub special_round: Option<usize>,
+    // next spawner of the round robin selection
+    pub spawner_index: usize,
+    // points shared by the players , spent on the map elements like the upgrade stations
+    pub points: u32,
+}
+
+impl ZombieGame {
+    pub fn countdown_seconds(&self, game_speed: &GameSpeed) -> f32 {
+        self.countdown as f32 * game_speed.0
+    }
+
+    // Set the enemies of the current round , from the special round scheduled or the zombie count
+    pub fn prepare_round(&mut self, config: &ZombieGameConfig) {
+        self.special_round = config.special_rounds.iter().position(|x| x.is_scheduled(self.round));
+        let count = match self.special_round {
+            Some(index) => config.special_rounds[index].enemy_count(config.players.len()),
+            None => config.zombie_count(self.round),
+        };
+        self.current_round = CurrentRoundInfo {
+            total_zombie: count,
+            zombie_remaining: count,
+        };
+    }
+}
+
+// Send when the zombies of a round start to spawn
+pub struct RoundStartEvent {
+    pub round: i32,
+}
+
+// Send when every zombie of a round are dead
+pub struct RoundEndEvent {
+    pub round: i32,
+}
+
+// Number of frames of a countdown of `seconds`
+fn countdown(game_speed: &GameSpeed, seconds: f32) -> u32 {
+    (seconds / game_speed.0).round().max(0.) as u32
+}
+
+// Last round state seen by `system_round_events`
+#[derive(Default)]
+pub struct RoundEventsState {
+    state: ZombieGameState,
+    round: i32,
+}
+
+// Send the events of the rounds when the state shown change , outside of the rollback
+// so a frame simulated again don't send them twice
+pub fn system_round_events(
+    q_zombie_game: Query<&ZombieGame>,
+    mut last: Local<RoundEventsState>,
+    mut ev_round_start: EventWriter<RoundStartEvent>,
+    mut ev_round_end: EventWriter<RoundEndEvent>,
+) {
+    let zombie_game = match q_zombie_game.get_single() {
+        Ok(zombie_game) => zombie_game,
+        Err(_) => return,
+    };
+
+    if zombie_game.state == last.state && zombie_game.round == last.round {
+        return;
+    }
+
+    match zombie_game.state {
+        ZombieGameState::Round => ev_round_start.send(RoundStartEvent { round: zombie_game.round }),
+        ZombieGameState::RoundInterlude if last.state == ZombieGameState::Round => ev_round_end.send(RoundEndEvent { round: zombie_game.round }),
+        _ => {}
+    }
+
+    last.state = zombie_game.state.clone();
+    last.round = zombie_game.round;
+}
+
+// Elements of the map and players the game spawn the enemies around
+#[derive(SystemParam)]
+pub struct ZombieGameQueries<'w, 's> {
+    spawner: Query<'w, 's, (Entity, &'static MapElementPosition, &'static mut ZombieSpawner)>,
+    window: Query<'w, 's, (&'static MapElementPosition, Entity), With<Window>>,
+    player_spawn: Query<'w, 's, (&'static MapElementPosition, &'static PlayerSpawn)>,
+    player: Query<'w, 's, (Entity, &'static Player, &'static Transform), Without<Death>>,
+    ammunition: Query<'w, 's, (&'static mut AmmunitionState, &'static Weapon)>,
 }
 
 
@@ -127,10 +474,51 @@ pub struct ZombieGameConfig {
     pub configuration: MapRoundConfiguration,
     pub starting_weapons: StartingWeapons,
     pub window_panel: WindowPanelConfiguration,
+    pub difficulty: DifficultyModifiers,
+    pub special_rounds: Vec<SpecialRoundConfiguration>,
+    pub scaling: RoundScaling,
 
     pub players: Vec<ZombiePlayerInformation>
 }
 
+impl ZombieGameConfig {
+    pub fn zombie_count(&self, round: i32) -> i32 {
+        match &self.scaling.zombie_count {
+            Some(curve) => curve.evaluate(round).round().max(0.) as i32,
+            None => self.configuration.starting_zombie + ((round - 1) * self.configuration.round_increments),
+        }
+    }
+
+    // seconds between two spawns of zombies
+    pub fn spawn_interval(&self, round: i32) -> f32 {
+        match &self.scaling.spawn_interval {
+            Some(curve) => curve.evaluate(round).round().max(1.) / 1000.,
+            None => self.configuration.initial_timeout as f32 / 1000.,
+        }
+    }
+
+    pub fn max_concurrent_zombie(&self, round: i32) -> i32 {
+        match &self.scaling.max_concurrent_zombie {
+            Some(curve) => curve.evaluate(round).round().max(1.) as i32,
+            None => self.difficulty.max_concurrent_zombie,
+        }
+    }
+
+    pub fn zombie_health(&self, round: i32) -> f32 {
+        match &self.scaling.zombie_health {
+            Some(curve) => curve.evaluate(round),
+            None => self.difficulty.zombie_health(round),
+        }
+    }
+
+    pub fn zombie_speed(&self, round: i32) -> f32 {
+        match &self.scaling.zombie_speed {
+            Some(curve) => curve.evaluate(round),
+            None => self.difficulty.zombie_speed(round),
+        }
+    }
+}
+
 
 pub struct ZombieGameStateChangeEvent {}
 pub struct ZombieGamePanelEvent {}
@@ -160,6 +548,9 @@ impl AssetLoader for ZombieLevelAssetLoader {
         &["level.ron"]
     }
 }
+pub const DEFAULT_MAP: &str = "maps/map_iso/iso_map.asset.ron";
+pub const DEFAULT_LEVEL: &str = "game/easy.level.ron";
+
 #[derive(Default)]
 pub struct LevelMapRequested {
     pub map: String,
@@ -175,10 +566,12 @@ impl Plugin for ZombieGamePlugin {
             .add_event::<ZombieGameStateChangeEvent>()
             .add_event::<ZombieGamePanelEvent>()
             .add_event::<PlayerDeadEvent>()
+            .add_event::<CameraShakeEvent>()
+            .add_event::<RoundStartEvent>()
+            .add_event::<RoundEndEvent>()
             .init_resource::<GameSpeed>()
             .init_resource::<ZombieGameConfig>()
             .init_resource::<ZombieLevelAssetState>()
-            .init_resource::<ZombieSpawnerConfig>()
 
             .add_system(change_game_state_event)
             .add_system(system_panel_event)
@@ -234,7 +627,6 @@ pub fn system_zombie_game(
 
     level_asset_state: Res<ZombieLevelAssetState>,
     custom_assets: ResMut<Assets<ZombieLevelAsset>>,
-    weapon_state: Res<WeaponAssetState>,
     map_state: Res<MapDataState>,
 
     mut q_zombie_game: Query<&mut ZombieGame>,
@@ -242,20 +634,16 @@ pub fn system_zombie_game(
 
     zombie_query: Query<&Zombie>,
 
-    time: Res<Time>,
-    mut config: ResMut<ZombieSpawnerConfig>,
 
     mut ev_panel_event: EventWriter<ZombieGamePanelEvent>,
-    
 
-    query_spawner: Query<&MapElementPosition, With<ZombieSpawner>>,
-    query_window: Query<(&MapElementPosition, Entity), With<Window>>,
+    mut queries: ZombieGameQueries,
 
     mut rip: ResMut<RollbackIdProvider>,
 
-    
-
     weapons: Res<WeaponAssetState>,
+
+    game_speed: Res<GameSpeed>,
 ) {
     let mut zombie_game = q_zombie_game.get_single_mut().unwrap();
     let mut nbr_zombie = 0;
@@ -269,70 +657,119 @@ pub fn system_zombie_game(
             if level_asset_state.loaded || data_asset.is_none() {
                 return;
             }
-            if weapon_state.loaded == false {
+            if weapons.loaded == false {
                 return;
             }
-            if map_state.rendered == false {
+            if !map_state.is_ready() {
                 println!("waiting for map");
                 return;
             }
             let data_asset = data_asset.unwrap();
 
-            zombie_game.round = 1;
-            zombie_game.current_round = CurrentRoundInfo {
-                total_zombie: zombie_game_config.configuration.starting_zombie,
-                zombie_remaining: zombie_game_config.configuration.starting_zombie,
-            };
-
             zombie_game_config.configuration = data_asset.configuration.clone();
             zombie_game_config.starting_weapons = data_asset.starting_weapons.clone();
             zombie_game_config.window_panel = data_asset.window_panel.clone();
- 
-            config.timer = Timer::new(
-                Duration::from_millis(zombie_game_config.configuration.initial_timeout),
-                true,
-            );
+            zombie_game_config.difficulty = data_asset.difficulty.clone();
+            zombie_game_config.special_rounds = data_asset.special_rounds.clone();
+            zombie_game_config.scaling = data_asset.scaling.clone();
+
+            zombie_game.round = 1;
+            zombie_game.points = zombie_game_config.configuration.starting_points;
+            zombie_game.prepare_round(&zombie_game_config);
 
             // creating event
             ev_panel_event.send(ZombieGamePanelEvent{});
 
-            // Spawn players
+            // Spawn players on the spawns of the map
+            let spawns = sorted_spawns(queries.player_spawn.iter());
             for player in zombie_game_config.players.iter() {
-                setup_player(&mut rip,&mut commands, &zombie_game_config, &weapons, player, player.index);
+                let position = select_player_spawn(&spawns, player.index);
+                setup_player(&mut rip,&mut commands, &zombie_game_config, &weapons, player, player.index, position);
             }
 
-            zombie_game.state = ZombieGameState::Round;
+            zombie_game.state = ZombieGameState::Starting;
+            zombie_game.countdown = countdown(&game_speed, zombie_game_config.configuration.starting_countdown);
         },
         ZombieGameState::Starting => {
+            if zombie_game.countdown > 0 {
+                zombie_game.countdown -= 1;
+                return;
+            }
 
+            zombie_game.state = ZombieGameState::Round;
+            zombie_game.spawn_countdown = countdown(&game_speed, zombie_game_config.spawn_interval(zombie_game.round));
         },
         ZombieGameState::Round => {
             if nbr_zombie == 0 && zombie_game.current_round.zombie_remaining == 0 {
                 zombie_game.state = ZombieGameState::RoundInterlude;
+                zombie_game.countdown = countdown(&game_speed, zombie_game_config.configuration.round_interlude);
+                zombie_game.points += zombie_game.current_round.total_zombie.max(0) as u32 * zombie_game_config.configuration.points_per_zombie;
+
+                // max ammo reward of the special rounds
+                if zombie_game.special_round.is_some() {
+                    for (mut ammunition_state, weapon) in queries.ammunition.iter_mut() {
+                        ammunition_state.refill(&weapon.ammunition);
+                    }
+                }
 
                 return;
             }
 
-            config.timer.tick(time.delta());
+            for (_, _, mut spawner) in queries.spawner.iter_mut() {
+                if spawner.cooldown_remaining > 0 {
+                    spawner.cooldown_remaining -= 1;
+                }
+            }
 
-            if config.timer.finished()
-                && zombie_game.current_round.zombie_remaining > 0
-                && nbr_zombie < 20
+            // the zombies spawn each time the countdown finish , it restart even when nothing can spawn
+            if zombie_game.spawn_countdown > 0 {
+                zombie_game.spawn_countdown -= 1;
+                return;
+            }
+            zombie_game.spawn_countdown = countdown(&game_speed, zombie_game_config.spawn_interval(zombie_game.round));
+
+            if zombie_game.current_round.zombie_remaining > 0
+                && nbr_zombie < zombie_game_config.max_concurrent_zombie(zombie_game.round)
             {
-                // TODO add better option to disable zombie spawning
-                for position in query_spawner.iter() {
+                if let Some(special) = zombie_game.special_round.map(|x| zombie_game_config.special_rounds[x].clone()) {
+                    spawn_special_enemy(&mut commands, &mut rip, &queries, &special, &mut zombie_game);
+                    return;
+                }
+
+                let mut players: Vec<(usize, Vec2)> = queries.player.iter().map(|(_, p, t)| (p.handle, t.translation.truncate())).collect();
+                players.sort_by_key(|x| x.0);
+                let players: Vec<Vec2> = players.into_iter().map(|x| x.1).collect();
+
+                let round_configuration = &zombie_game_config.configuration;
+                let zones = active_zones(&sorted_spawns(queries.player_spawn.iter()), &players);
+                let candidates = spawner_candidates(
+                    queries.spawner.iter(),
+                    if round_configuration.active_zones_only { Some(&zones) } else { None },
+                );
+                let seed = ((zombie_game.round as u32) << 16) ^ zombie_game.current_round.zombie_remaining as u32;
+                let selected = select_spawners(
+                    &round_configuration.spawner_selection,
+                    &candidates,
+                    &players,
+                    round_configuration.spawn_per_tick,
+                    &mut zombie_game.spawner_index,
+                    seed,
+                );
+
+                for index in selected {
                     if zombie_game.current_round.zombie_remaining > 0 {
-                        let mut ndg = rand::thread_rng();
-                        config.nums_ndg.shuffle(&mut ndg);
+                        let candidate = &candidates[index];
+                        if let Ok((_, _, mut spawner)) = queries.spawner.get_mut(candidate.entity) {
+                            spawner.cooldown_remaining = countdown(&game_speed, spawner.cooldown);
+                        }
 
-                        let position = position.position;
-                            //+ Vec2::new(config.nums_ndg[0] as f32, config.nums_ndg[50] as f32);
+                        let position = candidate.position;
                         let mut closest_window = MapElementPosition {
                             ..MapElementPosition::default()
                         };
                         let mut closest_window_entity: Entity = Entity::from_raw(0);
                         let mut closest_window_dst = 90000.;
-                        for (w, entity) in query_window.iter() {
+                        for (w, entity) in queries.window.iter() {
                             let distance = position.distance(w.position);
                             if distance < closest_window_dst {
                                 closest_window_dst = distance;
@@ -346,8 +783,10 @@ pub fn system_zombie_game(
                             entity: closest_window_entity.clone(),
                             path: vec![],
                             requested_movement: None, 
+                            ..default()
                         };
 
+                        bot_destination.speed = zombie_game_config.zombie_speed(zombie_game.round);
                         bot_destination.set_destination(closest_window.position, position, closest_window_entity.clone(), 0.);
 
                         commands.spawn().insert_bundle(ZombieBundle::new(
@@ -357,6 +796,9 @@ pub fn system_zombie_game(
                                 rotation: 0,
                             },
                             bot_destination,
+                            ZombieKind::Zombie,
+                            zombie_game_config.zombie_health(zombie_game.round),
+                            zombie_game_config.difficulty.zombie_damage,
                         )).insert(Rollback::new(rip.next_id()));
 
                         zombie_game.current_round.zombie_remaining -= 1;
@@ -367,21 +809,90 @@ pub fn system_zombie_game(
             }
         }
         ZombieGameState::RoundInterlude => {
+            if zombie_game.countdown > 0 {
+                zombie_game.countdown -= 1;
+                return;
+            }
+
             zombie_game.round += 1;
-            let zombie_count = zombie_game_config.configuration.starting_zombie
-                + ((zombie_game.round - 1) * zombie_game_config.configuration.round_increments);
-            zombie_game.current_round = CurrentRoundInfo {
-                zombie_remaining: zombie_count,
-                total_zombie: zombie_count,
-            };
+            zombie_game.prepare_round(&zombie_game_config);
             zombie_game.state = ZombieGameState::Round;
-            config.timer.reset();
+            zombie_game.spawn_countdown = countdown(&game_speed, zombie_game_config.spawn_interval(zombie_game.round));
         }
         ZombieGameState::Over => {}
     }
   
 }
 
+// distance to the targeted player under which the hellhounds don't spawn
+const HELLHOUND_SPAWN_MIN_DISTANCE: f32 = 150.;
+
+// Spawn one enemy of a special round , the hellhounds spawn on the player spawn
+// closest to a player and the boss enter by the windows like the zombies. The enemy
+// is always counted as spawned so the round can finish on a map without spawners
+fn spawn_special_enemy(
+    commands: &mut Commands,
+    rip: &mut RollbackIdProvider,
+    queries: &ZombieGameQueries,
+    special: &SpecialRoundConfiguration,
+    zombie_game: &mut ZombieGame,
+) {
+    let mut bot_destination = BotDestination {
+        speed: special.speed,
+        ..default()
+    };
+
+    let position = if special.kind.enter_by_windows() {
+        let spawners: Vec<Vec2> = spawner_candidates(queries.spawner.iter(), None).iter().map(|x| x.position).collect();
+        let position = if spawners.len() > 0 {
+            spawners[zombie_game.current_round.zombie_remaining as usize % spawners.len()]
+        } else {
+            sorted_spawns(queries.player_spawn.iter()).first().map(|x| x.0).unwrap_or(Vec2::ZERO)
+        };
+
+        let closest_window = queries.window.iter()
+            .min_by(|a, b| position.distance(a.0.position).partial_cmp(&position.distance(b.0.position)).unwrap());
+        if let Some((window, entity)) = closest_window {
+            bot_destination.set_destination(window.position, position, entity, 0.);
+        }
+        position
+    } else {
+        // sort to take the same player on every peer
+        let mut players: Vec<(Entity, usize, Vec2)> = queries.player.iter().map(|(e, p, t)| (e, p.handle, t.translation.truncate())).collect();
+        players.sort_by_key(|x| x.1);
+        if players.len() == 0 {
+            // nobody to hunt , the hellhound is skipped
+            zombie_game.current_round.zombie_remaining -= 1;
+            return;
+        }
+        let (target, _, target_position) = players[zombie_game.current_round.zombie_remaining as usize % players.len()];
+
+        let spawns = sorted_spawns(queries.player_spawn.iter());
+        let far_enough = spawns.iter()
+            .map(|x| x.0)
+            .filter(|x| x.distance(target_position) > HELLHOUND_SPAWN_MIN_DISTANCE)
+            .min_by(|a, b| a.distance(target_position).partial_cmp(&b.distance(target_position)).unwrap());
+        bot_destination.entity = target;
+        far_enough.or(spawns.first().map(|x| x.0)).unwrap_or(Vec2::ZERO)
+    };
+
+    commands.spawn().insert_bundle(ZombieBundle::new(
+        MapElementPosition {
+            position,
+            size: ZOMBIE_SIZE,
+            rotation: 0,
+        },
+        bot_destination,
+        special.kind,
+        special.health,
+        special.damage,
+    )).insert(Rollback::new(rip.next_id()));
+
+    zombie_game.current_round.zombie_remaining -= 1;
+
+    info!("Spawning {:?} , {} remaining", special.kind, zombie_game.current_round.zombie_remaining);
+}
+
 pub fn react_level_data(
     mut asset_events: EventReader<AssetEvent<ZombieLevelAsset>>,
     mut ev_state_change: EventWriter<ZombieGameStateChangeEvent>,
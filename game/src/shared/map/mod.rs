@@ -1,17 +1,19 @@
 mod loader;
-mod tiled_map;
+pub mod tiled_map;
 mod map_item_system;
 pub mod render;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod validation;
 
 use bevy::prelude::*;
 use bevy_ecs_tilemap::prelude::*;
 
 use tiled_map::{
     texture::set_texture_filters_to_nearest,
-    tiled::TiledMapPlugin,
+    tiled::{TiledMapPlugin, TiledMapAssetPlugin},
 };
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::shared::{collider::*, health::Health, game::GameState, player::interaction::{PlayerInteraction, PlayerInteractionType}};
 use loader::*;
@@ -21,9 +23,25 @@ pub struct MapPlugin {}
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<MapElementPosition>()
+        app.add_plugin(MapDataPlugin {})
             .add_plugin(TilemapPlugin)
             .add_plugin(TiledMapPlugin)
+
+            .add_system_set(
+                SystemSet::on_update(GameState::PlayingZombie)
+                    .with_system(set_texture_filters_to_nearest)
+            );
+   }
+}
+
+// Load the map data and spawn the map elements , without the rendering
+// of the tiled map so it can run headless
+pub struct MapDataPlugin {}
+
+impl Plugin for MapDataPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<MapElementPosition>()
+            .add_plugin(TiledMapAssetPlugin)
             .init_resource::<MapDataState>()
             .add_asset::<MapDataAsset>()
             .init_asset_loader::<MapDataAssetLoader>()
@@ -36,7 +54,7 @@ impl Plugin for MapPlugin {
                 SystemSet::on_update(GameState::PlayingZombie)
                     .with_system(react_event_scene)
                     .with_system(render_scene)
-                    .with_system(set_texture_filters_to_nearest)
+                    .with_system(spawn_tiled_map_objects)
                     .with_system(map_item_system::system_window_panel_destroy)
             );
    }
@@ -47,10 +65,14 @@ impl Plugin for MapPlugin {
 #[derive(Component, Default)]
 pub struct Size(pub Vec2);
 
+#[derive(Component, Default)]
+pub struct Wall {}
+
 #[derive(Bundle)]
 pub struct WallBundle {
     #[bundle]
     sprite_bundle: SpriteBundle,
+    wall: Wall,
     collider: MovementCollider,
     projectile_collider: ProjectileCollider,
     info: MapElementPosition,
@@ -83,10 +105,27 @@ pub struct WindowPanelBundle {
     size: Size,
 }
 
+// Station upgrading the active weapon of a player to its variant of `weapons.ron` , the price
+// is taken from the points of the players once the interaction is held for the duration
+#[derive(Component, Clone, Debug)]
+pub struct WeaponUpgradeStation {
+    pub price: u32,
+}
+
+#[derive(Bundle)]
+pub struct WeaponUpgradeStationBundle {
+    #[bundle]
+    sprite_bundle: SpriteBundle,
+    info: MapElementPosition,
+    map_element: MapElement,
+    station: WeaponUpgradeStation,
+    interaction: PlayerInteraction,
+}
+
 #[derive(Component)]
 pub struct MapElement {}
 
-#[derive(Component, Reflect, Default, Deserialize, Clone)]
+#[derive(Component, Reflect, Default, Deserialize, Serialize, Clone)]
 #[reflect(Component)]
 pub struct MapElementPosition {
     pub position: Vec2,
@@ -94,8 +133,23 @@ pub struct MapElementPosition {
     pub rotation: i32,
 }
 
-#[derive(Component, Default, Reflect)]
-pub struct ZombieSpawner {}
+// Location where the zombies spawn , the weight change how often the spawner is selected
+// , the zone limit it to the rounds where a player is in the zone and the cooldown is the
+// minimum seconds between two zombies
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct ZombieSpawner {
+    pub weight: f32,
+    pub zone: Option<String>,
+    pub cooldown: f32,
+    // frames before the spawner can spawn again
+    pub cooldown_remaining: u32,
+}
+
+impl Default for ZombieSpawner {
+    fn default() -> Self {
+        ZombieSpawner { weight: 1., zone: None, cooldown: 0., cooldown_remaining: 0 }
+    }
+}
 
 #[derive(Bundle)]
 pub struct ZombieSpawnerBundle {
@@ -106,8 +160,33 @@ pub struct ZombieSpawnerBundle {
     spawner: ZombieSpawner,
 }
 
+// Location where a player can spawn , the zone is use to respawn the dead
+// players near the alive ones and the team to split the players between spawns
+#[derive(Component, Default, Reflect, Clone, Debug)]
+pub struct PlayerSpawn {
+    pub zone: Option<String>,
+    pub team: Option<usize>,
+}
+
+#[derive(Bundle)]
+pub struct PlayerSpawnBundle {
+    position: MapElementPosition,
+    map_element: MapElement,
+    spawn: PlayerSpawn,
+}
+
+impl PlayerSpawnBundle {
+    pub fn new(info: MapElementPosition, spawn: PlayerSpawn) -> PlayerSpawnBundle {
+        PlayerSpawnBundle {
+            position: info,
+            map_element: MapElement {},
+            spawn: spawn,
+        }
+    }
+}
+
 impl ZombieSpawnerBundle {
-    pub fn new(info: MapElementPosition) -> ZombieSpawnerBundle {
+    pub fn new(info: MapElementPosition, spawner: ZombieSpawner) -> ZombieSpawnerBundle {
         ZombieSpawnerBundle {
             sprite_bundle: SpriteBundle {
                 sprite: Sprite {
@@ -123,7 +202,7 @@ impl ZombieSpawnerBundle {
             },
             position: info,
             map_element: MapElement {},
-            spawner: ZombieSpawner {},
+            spawner: spawner,
         }
     }
 }
@@ -163,6 +242,34 @@ impl WindowBundle {
     }
 }
 
+impl WeaponUpgradeStationBundle {
+    pub fn new(info: MapElementPosition, station: WeaponUpgradeStation, duration: f32) -> WeaponUpgradeStationBundle {
+        WeaponUpgradeStationBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.45, 0.15, 0.60),
+                    custom_size: Some(info.size),
+                    ..Sprite::default()
+                },
+                transform: Transform {
+                    translation: info.position.extend(10.0),
+                    ..Transform::default()
+                },
+                ..SpriteBundle::default()
+            },
+            interaction: PlayerInteraction {
+                interaction_available: true,
+                interaction_type: PlayerInteractionType::UpgradeWeapon,
+                interaction_size: info.size + Vec2::new(50., 50.),
+                interaction_timeout: duration,
+            },
+            info,
+            map_element: MapElement {},
+            station,
+        }
+    }
+}
+
 impl WindowPanelBundle {
     pub fn new(parent: MapElementPosition, index: u32, offset: f32) -> WindowPanelBundle {
         // need to find the direction vector of the window
@@ -212,6 +319,7 @@ impl WallBundle {
                 ..default()
             },
             projectile_collider: ProjectileCollider {},
+            wall: Wall {},
             info,
         }
     }
@@ -1,38 +1,201 @@
 use bevy::prelude::*;
 use bevy::reflect::TypeUuid;
 use bevy_ecs_tilemap::prelude::*;
+use bevy_ggrs::{Rollback, RollbackIdProvider};
 
 use crate::shared::collider::*;
-use super::tiled_map::tiled::{TiledMap, TiledMapBundle};
+use super::tiled_map::{tiled::{TiledMap, TiledMapBundle}, tiled_objects::{MapElementProperties, TiledMapObject, TILED_OBJECT_WALL, TILED_OBJECT_WINDOW, TILED_OBJECT_SPAWNER, TILED_OBJECT_PLAYER_SPAWN, TILED_OBJECT_UPGRADE_STATION}};
 use super::*;
-use crate::shared::game::LevelMapRequested;
-use serde::Deserialize;
+use crate::shared::game::{LevelMapRequested, AssetMetadata};
+use serde::{Deserialize, Serialize};
 
 #[derive(Default)]
 pub struct MapDataState {
     pub handle: Handle<MapDataAsset>,
     pub rendered: bool,
+    // the elements of the object layers of the tmx are spawned
+    pub tiled_objects_spawned: bool,
 }
 
-#[derive(Deserialize, Clone, Component)]
+impl MapDataState {
+    pub fn is_ready(&self) -> bool {
+        self.rendered && self.tiled_objects_spawned
+    }
+}
+
+// Marker of the map elements created from the object layers of the tmx
+#[derive(Component, Default)]
+pub struct TiledMapObjectElement {}
+
+#[derive(Deserialize, Serialize, Clone, Component)]
 pub struct MapTiledData {
     pub path: String,
     pub transform: Vec3,
 }
 
-#[derive(Deserialize, TypeUuid, Clone, Component)]
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MapPlayerSpawn {
+    pub position: Vec2,
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default)]
+    pub team: Option<usize>,
+}
+
+fn default_spawner_size() -> Vec2 {
+    Vec2::new(10., 10.)
+}
+
+fn default_spawner_weight() -> f32 {
+    1.
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MapZombieSpawner {
+    pub position: Vec2,
+    #[serde(default = "default_spawner_size")]
+    pub size: Vec2,
+    #[serde(default)]
+    pub rotation: i32,
+    #[serde(default = "default_spawner_weight")]
+    pub weight: f32,
+    #[serde(default)]
+    pub zone: Option<String>,
+    // seconds
+    #[serde(default)]
+    pub cooldown: f32,
+}
+
+impl MapZombieSpawner {
+    pub fn element(&self) -> MapElementPosition {
+        MapElementPosition { position: self.position, size: self.size, rotation: self.rotation }
+    }
+
+    pub fn spawner(&self) -> ZombieSpawner {
+        ZombieSpawner { weight: self.weight, zone: self.zone.clone(), cooldown: self.cooldown, ..default() }
+    }
+}
+
+fn default_upgrade_station_size() -> Vec2 {
+    Vec2::new(40., 40.)
+}
+
+fn default_upgrade_station_price() -> u32 {
+    500
+}
+
+fn default_upgrade_station_duration() -> f32 {
+    3.
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MapUpgradeStation {
+    pub position: Vec2,
+    #[serde(default = "default_upgrade_station_size")]
+    pub size: Vec2,
+    #[serde(default)]
+    pub rotation: i32,
+    #[serde(default = "default_upgrade_station_price")]
+    pub price: u32,
+    // seconds the interaction is held to upgrade the weapon
+    #[serde(default = "default_upgrade_station_duration")]
+    pub duration: f32,
+}
+
+impl MapUpgradeStation {
+    pub fn element(&self) -> MapElementPosition {
+        MapElementPosition { position: self.position, size: self.size, rotation: self.rotation }
+    }
+}
+
+#[derive(Deserialize, Serialize, TypeUuid, Clone, Component)]
 #[uuid = "39cadc56-aa9c-4543-8640-a018b74b5052"]
 pub struct MapDataAsset {
+    #[serde(default)]
+    pub metadata: AssetMetadata,
+    #[serde(default)]
     pub walls: Vec<MapElementPosition>,
+    #[serde(default)]
     pub windows: Vec<MapElementPosition>,
-    pub spawners: Vec<MapElementPosition>,
+    #[serde(default)]
+    pub spawners: Vec<MapZombieSpawner>,
+    #[serde(default)]
+    pub player_spawns: Vec<MapPlayerSpawn>,
+    #[serde(default)]
+    pub upgrade_stations: Vec<MapUpgradeStation>,
     pub tiled: MapTiledData,
 }
 
+// the cooldown of the spawner is part of the state of the game , it's rolled back
+pub fn spawn_spawner(command: &mut Commands, rip: &mut RollbackIdProvider, info: MapElementPosition, spawner: ZombieSpawner) -> Entity {
+    command
+        .spawn()
+        .insert_bundle(ZombieSpawnerBundle::new(info, spawner))
+        .insert(Rollback::new(rip.next_id())).id()
+}
+
+pub fn spawn_wall(command: &mut Commands, info: MapElementPosition) -> Entity {
+    command
+        .spawn()
+        .insert(MapElement {})
+        .insert_bundle(WallBundle::new(info)).id()
+}
+
+pub fn spawn_window(command: &mut Commands, info: MapElementPosition) -> Entity {
+    command
+        .spawn()
+        .insert(MapElement {})
+        .insert_bundle(WindowBundle::new(info)).id()
+}
+
+pub fn spawn_upgrade_station(command: &mut Commands, info: MapElementPosition, station: WeaponUpgradeStation, duration: f32) -> Entity {
+    command
+        .spawn()
+        .insert_bundle(WeaponUpgradeStationBundle::new(info, station, duration)).id()
+}
+
+pub fn spawn_player_spawn(command: &mut Commands, info: MapElementPosition, spawn: PlayerSpawn) -> Entity {
+    command
+        .spawn()
+        .insert_bundle(PlayerSpawnBundle::new(info, spawn)).id()
+}
+
+// Spawn the bundle matching the type of a object of the tmx
+pub fn spawn_tiled_object(command: &mut Commands, rip: &mut RollbackIdProvider, object: &TiledMapObject) -> Option<Entity> {
+    let position = object.position.clone();
+    let entity = match object.object_type.as_str() {
+        TILED_OBJECT_WALL => spawn_wall(command, position),
+        TILED_OBJECT_WINDOW => spawn_window(command, position),
+        TILED_OBJECT_SPAWNER => spawn_spawner(command, rip, position, ZombieSpawner {
+            weight: object.properties.get("weight").and_then(|w| w.parse().ok()).unwrap_or(1.),
+            zone: object.properties.get("zone").cloned(),
+            cooldown: object.properties.get("cooldown").and_then(|c| c.parse().ok()).unwrap_or(0.),
+            ..default()
+        }),
+        TILED_OBJECT_PLAYER_SPAWN => spawn_player_spawn(command, position, PlayerSpawn {
+            zone: object.properties.get("zone").cloned(),
+            team: object.properties.get("team").and_then(|t| t.parse().ok()),
+        }),
+        TILED_OBJECT_UPGRADE_STATION => spawn_upgrade_station(command, position, WeaponUpgradeStation {
+            price: object.properties.get("price").and_then(|p| p.parse().ok()).unwrap_or(default_upgrade_station_price()),
+        }, object.properties.get("duration").and_then(|d| d.parse().ok()).unwrap_or(default_upgrade_station_duration())),
+        _ => {
+            // doors and buy stations are not supported yet
+            println!("Unsupported tiled object type {} for {}", object.object_type, object.name);
+            return None;
+        }
+    };
+    command.entity(entity)
+        .insert(TiledMapObjectElement {})
+        .insert(MapElementProperties(object.properties.clone()));
+    Some(entity)
+}
+
 impl MapDataAsset {
     pub fn render(
         &self,
         command: &mut Commands,
+        rip: &mut RollbackIdProvider,
         asset_server: &AssetServer
     ) {
         let handle: Handle<TiledMap> = asset_server.load(self.tiled.path.as_str());
@@ -53,23 +216,23 @@ impl MapDataAsset {
             });
 
         for s in (&self.spawners).into_iter() {
-            command
-                .spawn()
-                .insert_bundle(ZombieSpawnerBundle::new(s.clone()));
+            spawn_spawner(command, rip, s.element(), s.spawner());
         }
 
         for w in (&self.walls).into_iter() {
-            command
-                .spawn()
-                .insert(MapElement {})
-                .insert_bundle(WallBundle::new(w.clone()));
+            spawn_wall(command, w.clone());
         }
 
         for w in (&self.windows).into_iter() {
-            let entity = command
-                .spawn()
-                .insert(MapElement {})
-                .insert_bundle(WindowBundle::new(w.clone())).id();
+            spawn_window(command, w.clone());
+        }
+
+        for p in (&self.player_spawns).into_iter() {
+            spawn_player_spawn(command, MapElementPosition { position: p.position, size: Vec2::new(50., 50.), rotation: 0 }, PlayerSpawn { zone: p.zone.clone(), team: p.team });
+        }
+
+        for u in (&self.upgrade_stations).into_iter() {
+            spawn_upgrade_station(command, u.element(), WeaponUpgradeStation { price: u.price }, u.duration);
         }
 
 
@@ -79,10 +242,11 @@ impl MapDataAsset {
 
 pub fn render_map_data(
     commands: &mut Commands,
+    rip: &mut RollbackIdProvider,
     map_data: &MapDataAsset,
     asset_server: &AssetServer,
 ) {
-    map_data.render(commands, asset_server);
+    map_data.render(commands, rip, asset_server);
 }
 
 pub fn load_scene_system(
@@ -94,6 +258,7 @@ pub fn load_scene_system(
     let handle: Handle<MapDataAsset> = asset_server.load(level_requested.map.as_str());
     state.handle = handle;
     state.rendered = false;
+    state.tiled_objects_spawned = false;
 
     asset_server.watch_for_changes().unwrap();
 }
@@ -102,6 +267,7 @@ pub fn render_scene(
     mut state: ResMut<MapDataState>,
     custom_assets: ResMut<Assets<MapDataAsset>>,
     mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
     asset_server: Res<AssetServer>,
 ) {
     let data_asset = custom_assets.get(&state.handle);
@@ -110,7 +276,7 @@ pub fn render_scene(
     }
     let map_data = data_asset.unwrap();
 
-    render_map_data(&mut commands, &map_data, &asset_server);
+    render_map_data(&mut commands, &mut rip, &map_data, &asset_server);
 
     state.rendered = true;
 }
@@ -128,10 +294,48 @@ pub fn react_event_scene(
                     commands.entity(element).despawn();
                 }
                 state.rendered = false;
+                state.tiled_objects_spawned = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+// Spawn the map elements of the object layers once the tmx is loaded ,
+// and spawn them again when the tmx is modified
+pub fn spawn_tiled_map_objects(
+    mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
+    mut state: ResMut<MapDataState>,
+    mut map_events: EventReader<AssetEvent<TiledMap>>,
+    tiled_maps: Res<Assets<TiledMap>>,
+    q_map: Query<(&Transform, &Handle<TiledMap>)>,
+    q_elements: Query<Entity, With<TiledMapObjectElement>>,
+) {
+    for event in map_events.iter() {
+        match event {
+            AssetEvent::Modified { .. } => {
+                for entity in q_elements.iter() {
+                    commands.entity(entity).despawn_recursive();
+                }
+                state.tiled_objects_spawned = false;
             }
             _ => {}
         }
     }
+
+    if !state.rendered || state.tiled_objects_spawned {
+        return;
+    }
+
+    for (transform, handle) in q_map.iter() {
+        if let Some(tiled_map) = tiled_maps.get(handle) {
+            for object in tiled_map.objects(transform.translation.truncate()).iter() {
+                spawn_tiled_object(&mut commands, &mut rip, object);
+            }
+            state.tiled_objects_spawned = true;
+        }
+    }
 }
 
 // unload the map and the map entity
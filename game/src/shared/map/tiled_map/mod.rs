@@ -1,3 +1,4 @@
 pub mod texture;
 pub mod tiled;
 pub mod tiled_usage;
+pub mod tiled_objects;
@@ -10,10 +10,19 @@ use bevy::reflect::TypeUuid;
 pub struct TiledMapPlugin;
 
 impl Plugin for TiledMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(process_loaded_tile_maps);
+    }
+}
+
+// Only load the tmx asset , to read the map objects without rendering the tiles
+#[derive(Default)]
+pub struct TiledMapAssetPlugin;
+
+impl Plugin for TiledMapAssetPlugin {
     fn build(&self, app: &mut App) {
         app.add_asset::<TiledMap>()
-            .add_asset_loader(TiledLoader)
-            .add_system(process_loaded_tile_maps);
+            .add_asset_loader(TiledLoader);
     }
 }
 
@@ -24,6 +33,22 @@ pub struct TiledMap {
     pub tilesets: HashMap<usize, Handle<Image>>,
 }
 
+impl TiledMap {
+    // Bounds of the map in world unit (min, max) when the map entity is at `origin`.
+    // For isometric maps the first tile is the top corner of the diamond
+    pub fn world_bounds(&self, origin: Vec2) -> (Vec2, Vec2) {
+        let (width, height) = (self.map.width as f32, self.map.height as f32);
+        let (tile_width, tile_height) = (self.map.tile_width as f32, self.map.tile_height as f32);
+        match self.map.orientation {
+            tiled::Orientation::Orthogonal => (origin, origin + Vec2::new(width * tile_width, height * tile_height)),
+            _ => (
+                origin - Vec2::new(height * tile_width / 2., 0.),
+                origin + Vec2::new(width * tile_width / 2., (width + height) * tile_height / 2.),
+            ),
+        }
+    }
+}
+
 #[derive(Default, Bundle)]
 pub struct TiledMapBundle {
     pub tiled_map: Handle<TiledMap>,
@@ -148,6 +173,11 @@ pub fn process_loaded_tile_maps(
                 for (tileset_index, tileset) in tiled_map.map.tilesets().iter().enumerate() {
                     // Once materials have been created/added we need to then create the layers.
                     for (layer_index, layer) in tiled_map.map.layers().enumerate() {
+                        // object layers are read as map elements , not rendered
+                        if !matches!(layer.layer_type(), tiled::LayerType::TileLayer(_)) {
+                            continue;
+                        }
+
                         let tile_width = tileset.tile_width as f32;
                         let tile_height = tileset.tile_height as f32;
 
@@ -0,0 +1,120 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::shared::map::MapElementPosition;
+
+use super::tiled::TiledMap;
+
+pub const TILED_OBJECT_WALL: &str = "wall";
+pub const TILED_OBJECT_WINDOW: &str = "window";
+pub const TILED_OBJECT_SPAWNER: &str = "spawner";
+pub const TILED_OBJECT_PLAYER_SPAWN: &str = "player_spawn";
+pub const TILED_OBJECT_UPGRADE_STATION: &str = "upgrade_station";
+
+// Gameplay element read from an object layer of the tmx
+#[derive(Clone, Debug)]
+pub struct TiledMapObject {
+    // type of the object , from the type of the object or the name of its layer
+    pub object_type: String,
+    pub name: String,
+    pub position: MapElementPosition,
+    // custom properties of the object
+    pub properties: HashMap<String, String>,
+}
+
+// Custom properties of the tiled object the map element was created from
+#[derive(Component, Default, Clone)]
+pub struct MapElementProperties(pub HashMap<String, String>);
+
+impl MapElementProperties {
+    pub fn get_f32(&self, name: &str) -> Option<f32> {
+        self.0.get(name).and_then(|x| x.parse::<f32>().ok())
+    }
+}
+
+fn property_to_string(value: &tiled::PropertyValue) -> String {
+    match value {
+        tiled::PropertyValue::BoolValue(v) => v.to_string(),
+        tiled::PropertyValue::FloatValue(v) => v.to_string(),
+        tiled::PropertyValue::IntValue(v) => v.to_string(),
+        tiled::PropertyValue::ColorValue(v) => v.to_string(),
+        tiled::PropertyValue::StringValue(v) => v.clone(),
+        tiled::PropertyValue::FileValue(v) => v.clone(),
+        tiled::PropertyValue::ObjectValue(v) => v.to_string(),
+    }
+}
+
+// the layer can be named in plural (walls , windows) for the object without type
+fn layer_name_to_object_type(layer_name: &str) -> String {
+    let name = layer_name.to_lowercase();
+    name.strip_suffix('s').map(|x| x.to_string()).unwrap_or(name)
+}
+
+impl TiledMap {
+
+    // Convert a position in the tmx pixel space to the world , when the map entity is at `origin`
+    fn tiled_to_world(&self, origin: Vec2, position: Vec2) -> Vec2 {
+        let tile_width = self.map.tile_width as f32;
+        let tile_height = self.map.tile_height as f32;
+        match self.map.orientation {
+            tiled::Orientation::Orthogonal => {
+                let map_height = self.map.height as f32 * tile_height;
+                origin + Vec2::new(position.x, map_height - position.y)
+            },
+            _ => {
+                // isometric object are place in tile unit of tile_height pixels on both axis
+                let tile_x = position.x / tile_height;
+                let tile_y = position.y / tile_height;
+                let map_height = (self.map.width + self.map.height) as f32 * tile_height / 2.;
+                origin + Vec2::new((tile_x - tile_y) * tile_width / 2., map_height - (tile_x + tile_y) * tile_height / 2.)
+            }
+        }
+    }
+
+    // Size in the world of a rectangle of the tmx , on isometric map the rectangle
+    // become a diamond and the size is the one of the box around it
+    fn tiled_size_to_world(&self, size: Vec2) -> Vec2 {
+        match self.map.orientation {
+            tiled::Orientation::Orthogonal => size,
+            _ => {
+                let tile_width = self.map.tile_width as f32;
+                let tile_height = self.map.tile_height as f32;
+                let tiles = (size.x + size.y) / tile_height;
+                Vec2::new(tiles * tile_width / 2., tiles * tile_height / 2.)
+            }
+        }
+    }
+
+    // Read every object of the object layers of the map
+    pub fn objects(&self, origin: Vec2) -> Vec<TiledMapObject> {
+        let mut objects = vec![];
+        for layer in self.map.layers() {
+            if let tiled::LayerType::ObjectLayer(object_layer) = layer.layer_type() {
+                for object in object_layer.objects() {
+                    let size = match object.shape {
+                        tiled::ObjectShape::Rect { width, height } => Vec2::new(width, height),
+                        tiled::ObjectShape::Ellipse { width, height } => Vec2::new(width, height),
+                        _ => Vec2::new(10., 10.),
+                    };
+
+                    let object_type = if object.obj_type.is_empty() {
+                        layer_name_to_object_type(layer.name.as_str())
+                    } else { object.obj_type.to_lowercase() };
+
+                    let center = self.tiled_to_world(origin, Vec2::new(object.x, object.y) + size / 2.);
+
+                    objects.push(TiledMapObject {
+                        object_type,
+                        name: object.name.clone(),
+                        position: MapElementPosition {
+                            position: center,
+                            size: self.tiled_size_to_world(size),
+                            rotation: object.rotation as i32,
+                        },
+                        properties: object.properties.iter().map(|(k, v)| (k.clone(), property_to_string(v))).collect(),
+                    });
+                }
+            }
+        }
+        objects
+    }
+}
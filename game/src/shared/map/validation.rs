@@ -0,0 +1,287 @@
+use std::{collections::{HashMap, HashSet}, path::Path};
+
+use bevy::prelude::*;
+use pathfinding::prelude::bfs_reach;
+
+use crate::shared::{
+    game::ZombieLevelAsset,
+    player::PLAYER_SIZE,
+    utils::asset_root,
+    weapons::{loader::WeaponsAsset, equipment::EquipmentAsset},
+    zombies::zombie::ZOMBIE_SIZE,
+};
+
+use super::{
+    MapElementPosition,
+    render::MapDataAsset,
+    tiled_map::{tiled::TiledMap, tiled_objects::{TILED_OBJECT_WALL, TILED_OBJECT_WINDOW, TILED_OBJECT_SPAWNER, TILED_OBJECT_PLAYER_SPAWN, TILED_OBJECT_UPGRADE_STATION}},
+};
+
+const WEAPONS_ASSET: &str = "weapons/weapons.ron";
+const EQUIPMENT_ASSET: &str = "weapons/default.equipment.ron";
+
+// size of a cell of the grid used to find if an element can reach an other one
+const VALIDATION_CELL_SIZE: f32 = 10.;
+// space around the map elements where the grid extend
+const VALIDATION_MARGIN: f32 = 200.;
+
+pub struct MapValidationArgs {
+    pub map: String,
+    pub level: String,
+}
+
+impl MapValidationArgs {
+    // Read `--validate-map <map> [--level <level>]` , the paths are relative to the assets folder
+    pub fn from_args(default_level: &str) -> Option<MapValidationArgs> {
+        let args: Vec<String> = std::env::args().collect();
+        let index = args.iter().position(|x| x == "--validate-map")?;
+        let map = args.get(index + 1)?.clone();
+        let level = args.iter().position(|x| x == "--level")
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+            .unwrap_or(default_level.to_string());
+        Some(MapValidationArgs { map, level })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MapIssueLevel {
+    Warning,
+    Error,
+}
+
+#[derive(Debug)]
+pub struct MapIssue {
+    pub level: MapIssueLevel,
+    pub message: String,
+}
+
+#[derive(Default, Debug)]
+pub struct MapValidationReport {
+    pub issues: Vec<MapIssue>,
+}
+
+impl MapValidationReport {
+    fn warning(&mut self, message: String) {
+        self.issues.push(MapIssue { level: MapIssueLevel::Warning, message });
+    }
+
+    fn error(&mut self, message: String) {
+        self.issues.push(MapIssue { level: MapIssueLevel::Error, message });
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|x| x.level == MapIssueLevel::Error)
+    }
+
+    pub fn print(&self) {
+        for issue in self.issues.iter() {
+            println!("{:?} : {}", issue.level, issue.message);
+        }
+    }
+}
+
+fn read_ron<T: for<'de> serde::Deserialize<'de>>(path: &Path) -> Result<T, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("can't read {:?} : {}", path, err))?;
+    ron::de::from_bytes::<T>(&bytes).map_err(|err| format!("can't parse {:?} : {}", path, err))
+}
+
+fn overlap(a: &MapElementPosition, b: &MapElementPosition) -> bool {
+    let distance = (a.position - b.position).abs();
+    let size = (a.size + b.size) / 2.;
+    distance.x < size.x && distance.y < size.y
+}
+
+fn contains(element: &MapElementPosition, point: Vec2, margin: Vec2) -> bool {
+    let distance = (element.position - point).abs();
+    let size = element.size / 2. + margin;
+    distance.x <= size.x && distance.y <= size.y
+}
+
+// Grid of the map where the walls and windows block the movement of an agent of `agent_size`
+struct ReachGrid<'a> {
+    min: Vec2,
+    max: Vec2,
+    agent_size: Vec2,
+    obstacles: Vec<&'a MapElementPosition>,
+}
+
+impl<'a> ReachGrid<'a> {
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        let cell = (position - self.min) / VALIDATION_CELL_SIZE;
+        (cell.x as i32, cell.y as i32)
+    }
+
+    fn cell_center(&self, cell: (i32, i32)) -> Vec2 {
+        self.min + (Vec2::new(cell.0 as f32, cell.1 as f32) + 0.5) * VALIDATION_CELL_SIZE
+    }
+
+    fn is_free(&self, cell: (i32, i32)) -> bool {
+        let center = self.cell_center(cell);
+        if center.x < self.min.x || center.y < self.min.y || center.x > self.max.x || center.y > self.max.y {
+            return false;
+        }
+        !self.obstacles.iter().any(|x| contains(x, center, self.agent_size / 2.))
+    }
+
+    // Cells the agent can reach from `start`
+    fn reach(&self, start: Vec2) -> HashSet<(i32, i32)> {
+        bfs_reach(self.cell(start), |&(x, y)| {
+            vec![(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|x| self.is_free(*x))
+                .collect::<Vec<_>>()
+        }).collect()
+    }
+
+    // The agent is close enough to the window to interact with it
+    fn can_reach(&self, reached: &HashSet<(i32, i32)>, element: &MapElementPosition) -> bool {
+        let margin = self.agent_size / 2. + VALIDATION_CELL_SIZE;
+        reached.iter().any(|x| contains(element, self.cell_center(*x), margin))
+    }
+}
+
+// Load the map data , its tmx and the level from the assets folder and look for the
+// errors that would only show up while playing
+pub fn validate_map(map_path: &str, level_path: &str) -> Result<MapValidationReport, String> {
+    let root = asset_root();
+    let mut report = MapValidationReport::default();
+
+    let map_data: MapDataAsset = read_ron(&root.join(map_path))?;
+
+    let mut walls = map_data.walls.clone();
+    let mut windows = map_data.windows.clone();
+    let mut spawners: Vec<MapElementPosition> = map_data.spawners.iter().map(|x| x.element()).collect();
+    let mut player_spawns: Vec<MapElementPosition> = map_data.player_spawns.iter()
+        .map(|x| MapElementPosition { position: x.position, size: PLAYER_SIZE, rotation: 0 })
+        .collect();
+    let mut upgrade_stations: Vec<MapElementPosition> = map_data.upgrade_stations.iter().map(|x| x.element()).collect();
+
+    let mut bounds: Option<(Vec2, Vec2)> = None;
+
+    let tmx_path = root.join(map_data.tiled.path.as_str());
+    match tiled::Loader::new().load_tmx_map(&tmx_path) {
+        Ok(map) => {
+            for tileset in map.tilesets().iter() {
+                match tileset.image.as_ref() {
+                    Some(image) => if !image.source.exists() && !root.join(&image.source).exists() {
+                        report.error(format!("Missing texture {:?} of the tileset {}", image.source, tileset.name));
+                    },
+                    None => report.error(format!("The tileset {} has no image", tileset.name)),
+                }
+            }
+
+            let tiled_map = TiledMap { map, tilesets: HashMap::default() };
+            let origin = map_data.tiled.transform.truncate();
+            bounds = Some(tiled_map.world_bounds(origin));
+
+            for object in tiled_map.objects(origin) {
+                match object.object_type.as_str() {
+                    TILED_OBJECT_WALL => walls.push(object.position),
+                    TILED_OBJECT_WINDOW => windows.push(object.position),
+                    TILED_OBJECT_SPAWNER => spawners.push(object.position),
+                    TILED_OBJECT_PLAYER_SPAWN => player_spawns.push(object.position),
+                    TILED_OBJECT_UPGRADE_STATION => upgrade_stations.push(object.position),
+                    _ => report.warning(format!("Unsupported tiled object type {} for {}", object.object_type, object.name)),
+                }
+            }
+        },
+        Err(err) => report.error(format!("Can't load the tmx {:?} : {}", tmx_path, err)),
+    }
+
+    match read_ron::<ZombieLevelAsset>(&root.join(level_path)) {
+        Ok(level) => {
+            match read_ron::<WeaponsAsset>(&root.join(WEAPONS_ASSET)) {
+                Ok(weapons) => {
+                    let mut starting_weapons = vec![level.starting_weapons.starting_weapon.clone()];
+                    starting_weapons.extend(level.starting_weapons.starting_alternate_weapon.clone());
+                    for name in starting_weapons.iter() {
+                        if !weapons.weapons.iter().any(|w| w.name.eq(name)) {
+                            report.error(format!("Unknown starting weapon {} in {}", name, level_path));
+                        }
+                    }
+                    for weapon in weapons.weapons.iter() {
+                        if let Some(base) = weapon.upgrade_of.as_ref() {
+                            if !weapons.weapons.iter().any(|w| w.name.eq(base)) {
+                                report.error(format!("The weapon {} is the upgrade of the unknown weapon {}", weapon.name, base));
+                            }
+                        }
+                    }
+                },
+                Err(err) => report.error(err),
+            }
+            if let Some(name) = level.starting_weapons.starting_equipment.as_ref() {
+                match read_ron::<EquipmentAsset>(&root.join(EQUIPMENT_ASSET)) {
+                    Ok(equipments) => if !equipments.equipments.iter().any(|e| e.name.eq(name)) {
+                        report.error(format!("Unknown starting equipment {} in {}", name, level_path));
+                    },
+                    Err(err) => report.error(err),
+                }
+            }
+            let melee = &level.starting_weapons.starting_melee;
+            match read_ron::<EquipmentAsset>(&root.join(EQUIPMENT_ASSET)) {
+                Ok(equipments) => if !equipments.melees.iter().any(|m| m.name.eq(melee)) {
+                    report.error(format!("Unknown starting melee weapon {} in {}", melee, level_path));
+                },
+                Err(err) => report.error(err),
+            }
+        },
+        Err(err) => report.error(err),
+    }
+
+    for (i, a) in walls.iter().enumerate() {
+        for b in walls.iter().skip(i + 1) {
+            if overlap(a, b) {
+                report.warning(format!("Wall at {} overlap the wall at {}", a.position, b.position));
+            }
+        }
+    }
+
+    if windows.len() == 0 {
+        report.error("The map has no window".to_string());
+    }
+    if spawners.len() == 0 {
+        report.error("The map has no zombie spawner".to_string());
+    }
+    if player_spawns.len() == 0 {
+        report.warning("The map has no player spawn , the players start around the origin".to_string());
+        player_spawns.push(MapElementPosition { position: Vec2::ZERO, size: PLAYER_SIZE, rotation: 0 });
+    }
+
+    // extend the grid to every element in case they are outside of the tmx
+    let (mut min, mut max) = bounds.unwrap_or((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)));
+    for element in walls.iter().chain(windows.iter()).chain(spawners.iter()).chain(player_spawns.iter()).chain(upgrade_stations.iter()) {
+        min = min.min(element.position - element.size / 2.);
+        max = max.max(element.position + element.size / 2.);
+    }
+    min -= Vec2::splat(VALIDATION_MARGIN);
+    max += Vec2::splat(VALIDATION_MARGIN);
+
+    let obstacles: Vec<&MapElementPosition> = walls.iter().chain(windows.iter()).collect();
+
+    let zombie_grid = ReachGrid { min, max, agent_size: ZOMBIE_SIZE, obstacles: obstacles.clone() };
+    for spawner in spawners.iter() {
+        let reached = zombie_grid.reach(spawner.position);
+        if !windows.iter().any(|w| zombie_grid.can_reach(&reached, w)) {
+            report.error(format!("The spawner at {} can't reach any window", spawner.position));
+        }
+    }
+
+    let player_grid = ReachGrid { min, max, agent_size: PLAYER_SIZE, obstacles };
+    let mut reached = HashSet::new();
+    for spawn in player_spawns.iter() {
+        reached.extend(player_grid.reach(spawn.position));
+    }
+    for window in windows.iter() {
+        if !player_grid.can_reach(&reached, window) {
+            report.error(format!("The window at {} can't be reached by the players", window.position));
+        }
+    }
+    for station in upgrade_stations.iter() {
+        if !player_grid.can_reach(&reached, station) {
+            report.error(format!("The upgrade station at {} can't be reached by the players", station.position));
+        }
+    }
+
+    Ok(report)
+}
@@ -6,6 +6,7 @@ use bevy::{
 
 use bevy_ggrs::{Rollback, RollbackIdProvider};
 use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
 use ggrs::{Config, InputStatus, P2PSession, PlayerHandle, SpectatorSession, SyncTestSession};
 use std::{hash::Hash};
 
@@ -31,10 +32,10 @@ impl ggrs::Config for GGRSConfig {
     type Address = String;
 }
 
-const INPUT_UP: i32 = 1 << 0;
-const INPUT_DOWN: i32 = 1 << 1;
-const INPUT_LEFT: i32 = 1 << 2;
-const INPUT_RIGHT: i32 = 1 << 3;
+pub const INPUT_UP: i32 = 1 << 0;
+pub const INPUT_DOWN: i32 = 1 << 1;
+pub const INPUT_LEFT: i32 = 1 << 2;
+pub const INPUT_RIGHT: i32 = 1 << 3;
 pub const INPUT_FIRE: i32 = 1 << 4;
 pub const INPUT_JUST_FIRE: i32 = 1 << 5;
 
@@ -44,11 +45,21 @@ pub const INPUT_WEAPON_CHANGED: i32 = 1 << 7;
 
 pub const INPUT_INTERACTION_PRESSED: i32 = 1 << 8;
 
+pub const INPUT_THROW_EQUIPMENT: i32 = 1 << 9;
+
+pub const INPUT_MELEE: i32 = 1 << 10;
+
+pub const INPUT_WEAPON_PREVIOUS: i32 = 1 << 11;
+
+// slot selected directly , 0 when no slot is selected or the slot number from 1
+pub const INPUT_WEAPON_SLOT_SHIFT: i32 = 12;
+pub const INPUT_WEAPON_SLOT_MASK: i32 = 0b11 << INPUT_WEAPON_SLOT_SHIFT;
+
 
 pub const INPUT_FROM_GAMEPAD: i32 = 1 << 31;
 
 #[repr(C)]
-#[derive(Copy, Clone, PartialEq, Pod, Zeroable, Default)]
+#[derive(Copy, Clone, PartialEq, Pod, Zeroable, Default, Serialize, Deserialize)]
 pub struct BoxInput {
     // 0 : UP
     // 1 : DOWN
@@ -58,6 +69,11 @@ pub struct BoxInput {
     // 5 : Reload
     // 6 : Change Weapon
     // 7 : Action
+    // 8 : Interaction
+    // 9 : Throw equipment
+    // 10 : Melee
+    // 11 : Previous weapon
+    // 12-13 : Weapon slot
     // ..
     // ..
     // 
@@ -173,6 +189,20 @@ pub fn input(
                 if keyboard_input.pressed(KeyCode::F) {
                     input |= INPUT_INTERACTION_PRESSED;
                 }
+                if keyboard_input.just_pressed(KeyCode::G) {
+                    input |= INPUT_THROW_EQUIPMENT;
+                }
+                if keyboard_input.just_pressed(KeyCode::V) {
+                    input |= INPUT_MELEE;
+                }
+                if keyboard_input.just_pressed(KeyCode::Q) {
+                    input |= INPUT_WEAPON_PREVIOUS;
+                }
+                for (slot, key) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3].iter().enumerate() {
+                    if keyboard_input.just_pressed(*key) {
+                        input |= ((slot as i32 + 1) << INPUT_WEAPON_SLOT_SHIFT) & INPUT_WEAPON_SLOT_MASK;
+                    }
+                }
 
                 mouse_position = get_cursor_location(&wnds, &q_camera);
             } else {
@@ -211,6 +241,9 @@ pub fn input(
                 let change_weapon_button = GamepadButton(player_gamepad, GamepadButtonType::North);
                 let interaction_button = GamepadButton(player_gamepad, GamepadButtonType::South);
                 let weapon_trigger_button = GamepadButton(player_gamepad, GamepadButtonType::RightTrigger);
+                let equipment_button = GamepadButton(player_gamepad, GamepadButtonType::LeftTrigger);
+                let melee_button = GamepadButton(player_gamepad, GamepadButtonType::RightThumb);
+                let previous_weapon_button = GamepadButton(player_gamepad, GamepadButtonType::DPadDown);
 
                 if buttons.pressed(weapon_trigger_button) {
                     input |= INPUT_FIRE
@@ -227,6 +260,20 @@ pub fn input(
                 if buttons.pressed(interaction_button) {
                     input |= INPUT_INTERACTION_PRESSED;
                 }
+                if buttons.just_pressed(equipment_button) {
+                    input |= INPUT_THROW_EQUIPMENT;
+                }
+                if buttons.just_pressed(melee_button) {
+                    input |= INPUT_MELEE;
+                }
+                if buttons.just_pressed(previous_weapon_button) {
+                    input |= INPUT_WEAPON_PREVIOUS;
+                }
+                for (slot, button) in [GamepadButtonType::DPadLeft, GamepadButtonType::DPadUp, GamepadButtonType::DPadRight].iter().enumerate() {
+                    if buttons.just_pressed(GamepadButton(player_gamepad, *button)) {
+                        input |= ((slot as i32 + 1) << INPUT_WEAPON_SLOT_SHIFT) & INPUT_WEAPON_SLOT_MASK;
+                    }
+                }
 
             }
         }
@@ -1,7 +1,10 @@
 use bevy::{prelude::*, sprite::collide_aabb::collide};
 use ggrs::InputStatus;
 
-use crate::shared::{map::{MapElementPosition, Window, WindowPanel, Size}, health::Health, weapons::weapons::{PlayerInputs, INTERACTION_BTN}};
+use crate::shared::{
+    map::{MapElementPosition, Window, WindowPanel, Size, WeaponUpgradeStation}, health::Health, game::ZombieGame,
+    weapons::{weapons::{PlayerInputs, INTERACTION_BTN, Weapon, AmmunitionState, ActiveWeapon}, loader::WeaponAssetState},
+};
 
 use super::{Player, input::{PlayerCurrentInput, INPUT_INTERACTION_PRESSED, BoxInput}};
 
@@ -30,6 +33,7 @@ pub enum PlayerInteractionType {
     None = 0,
 
     RepairWindow,
+    UpgradeWeapon,
 }
 
 #[derive(Default, Component)]
@@ -44,8 +48,14 @@ pub struct PlayerInteraction {
     pub interaction_timeout: f32
 }
 
+// Replace the weapon by its upgraded variant , the ammunition in the magasin and the reserve are kept
+pub fn upgrade_weapon(weapon: &mut Weapon, ammo_state: &mut AmmunitionState, variant: &Weapon) {
+    *weapon = variant.clone();
+    ammo_state.clamp_to_limit(&weapon.ammunition);
+}
+
 pub fn system_interaction_player(
-    mut query_player: Query<(&Transform, &mut PlayerCurrentInteraction, &PlayerCurrentInput, &Player)>,
+    mut query_player: Query<(&Transform, &mut PlayerCurrentInteraction, &PlayerCurrentInput, &Player, &Children)>,
     time: Res<Time>,
     interaction_query: Query<
         (Entity, &Transform, &MapElementPosition, &PlayerInteraction),
@@ -58,10 +68,15 @@ pub fn system_interaction_player(
     inputs: Res<Vec<(BoxInput, InputStatus)>>,
 
     mut query_window: Query<(&mut Window, &mut Health, &Children)>,
-    mut query_panel: Query<(&mut WindowPanel, &Size, &mut Sprite)>
+    mut query_panel: Query<(&mut WindowPanel, &Size, &mut Sprite)>,
+
+    query_station: Query<&WeaponUpgradeStation>,
+    mut query_weapon: Query<(&mut Weapon, &mut AmmunitionState), With<ActiveWeapon>>,
+    mut q_zombie_game: Query<&mut ZombieGame>,
+    weapons: Res<WeaponAssetState>,
 ) {
 
-    for (player_transform, mut interaction, current_input, player) in query_player.iter_mut() {
+    for (player_transform, mut interaction, current_input, player, childrens) in query_player.iter_mut() {
 
         if inputs.len() <= player.handle {
             continue;
@@ -141,6 +156,37 @@ pub fn system_interaction_player(
                             }
                         }
                     },
+                    PlayerInteractionType::UpgradeWeapon => {
+                        let price = query_station.get(interaction.entity).map(|x| x.price).unwrap_or(0);
+                        let mut zombie_game = match q_zombie_game.get_single_mut() {
+                            Ok(zombie_game) => zombie_game,
+                            Err(_) => continue,
+                        };
+                        let active_weapon = childrens.iter().find(|x| query_weapon.get(**x).is_ok()).cloned();
+                        let (mut weapon, mut ammo_state) = match active_weapon.and_then(|x| query_weapon.get_mut(x).ok()) {
+                            Some(active_weapon) => active_weapon,
+                            None => continue,
+                        };
+                        let variant = match weapons.upgrade_of(weapon.name.as_str()) {
+                            Some(variant) => variant,
+                            None => continue,
+                        };
+
+                        if interaction.interacting == true {
+                            // the weapon is upgraded once the interaction is held for the duration of the station
+                            let time_since_startup = time.time_since_startup().as_secs_f32();
+                            if interaction.interaction_trigger_at + interaction.interaction_cooldown <= time_since_startup {
+                                interaction.interacting = false;
+                                if zombie_game.points >= price {
+                                    zombie_game.points -= price;
+                                    upgrade_weapon(&mut weapon, &mut ammo_state, variant);
+                                }
+                            }
+                        } else if zombie_game.points >= price {
+                            interaction.interacting = true;
+                            interaction.interaction_trigger_at = time.time_since_startup().as_secs_f32();
+                        }
+                    },
                     _ => {}
                 }
             } else {
@@ -1,36 +1,23 @@
 pub mod interaction;
 pub mod input;
+pub mod spawn;
 
 use bevy::{prelude::*, math::const_vec2};
 use bevy_ggrs::{Rollback, RollbackIdProvider};
 
 use crate::shared::{
     collider::{MovementCollider, is_colliding},
-    game::{ZombieGame, GameState, GameSpeed, ZombiePlayerInformation, ZombieGameConfig},
-    map::{MapElementPosition},
-    utils::get_cursor_location, weapons::{weapons::{WeaponBundle, ActiveWeapon}, loader::WeaponAssetState}, animation::AnimationTimer, character::{LookingAt, CharacterMovementState, Death, Velocity}, health::{Health, HealthChangeState, HealthRegeneration}
+    game::{ZombieGame, ZombieGameState, GameState, GameSpeed, ZombiePlayerInformation, ZombieGameConfig, DifficultyModifiers},
+    map::{MapElementPosition, PlayerSpawn},
+    zombies::zombie::Zombie,
+    utils::{get_cursor_location, Checksum}, weapons::{weapons::{WeaponBundle, ActiveWeapon, Weapon, WeaponSlot, WeaponSlots}, loader::WeaponAssetState, equipment::LethalEquipment, melee::MeleeWeapon}, animation::AnimationTimer, character::{LookingAt, CharacterMovementState, Death, Velocity}, health::{Health, HealthChangeState, HealthRegeneration}
 };
 
-use self::{interaction::{PlayerCurrentInteraction, PlayerInteractionType}, input::{PlayerCurrentInput, AvailableGameController}};
+use self::{interaction::{PlayerCurrentInteraction, PlayerInteractionType}, input::{PlayerCurrentInput, AvailableGameController}, spawn::{sorted_spawns, select_respawn}};
 
 
 pub const PLAYER_SIZE: Vec2 = const_vec2!([25., 25.]);
 
-const SPAWN_OFFSET_0: Vec2 = const_vec2!([-50., 50.]);
-const SPAWN_OFFSET_1: Vec2 = const_vec2!([50., 50.]);
-const SPAWN_OFFSET_2: Vec2 = const_vec2!([-50., -50.]);
-const SPAWN_OFFSET_3: Vec2 = const_vec2!([50., -50.]);
-
-fn get_spawn_offset(player_index: usize) -> Vec2 {
-    return match player_index {
-        0=> SPAWN_OFFSET_0,
-        1=> SPAWN_OFFSET_1,
-        2=> SPAWN_OFFSET_2,
-        3=> SPAWN_OFFSET_3,
-        _=> Vec2::default(),
-    };
-}
-
 #[derive(Component)]
 pub struct MainCamera;
 
@@ -44,6 +31,11 @@ pub struct PlayerDeadEvent {
     pub player: Entity,
 }
 
+// Add trauma to the camera of the local players to shake it , the trauma is between 0 and 1
+pub struct CameraShakeEvent {
+    pub trauma: f32,
+}
+
 #[derive(Bundle)]
 pub struct PlayerBundle {
     pub player: Player,
@@ -60,10 +52,11 @@ pub struct PlayerBundle {
 
     pub velocity: Velocity,
     pub player_current_input: PlayerCurrentInput,
+    pub checksum: Checksum,
 }
 
 impl PlayerBundle {
-    fn new(starting_weapon_name: &str, input: PlayerCurrentInput, index_player: usize, is_local: bool) -> PlayerBundle {
+    fn new(starting_weapon_name: &str, input: PlayerCurrentInput, index_player: usize, is_local: bool, position: Vec2, difficulty: &DifficultyModifiers) -> PlayerBundle {
         PlayerBundle { 
             player: Player{
                 handle: index_player,
@@ -73,7 +66,7 @@ impl PlayerBundle {
             player_current_input: input,
             sprite : SpriteSheetBundle {
                 transform: Transform {
-                    translation: Vec3::new(0., 0., 10.) + get_spawn_offset(index_player).extend(0.),
+                    translation: position.extend(10.),
                     ..Transform::default()
                 },
                 ..default()
@@ -94,10 +87,15 @@ impl PlayerBundle {
                 asset_type: "player".to_string(),
                 current_state: "".to_string(),
             },
-            health: Health { current_health: 3., tmp_health: 3., max_health: 3., ..default() },
+            health: Health {
+                current_health: difficulty.player_max_health,
+                tmp_health: difficulty.player_max_health,
+                max_health: difficulty.player_max_health,
+                ..default()
+            },
             health_regeneration: HealthRegeneration{
-                timeout_regeneration: 2.,
-                regeneration_amount: 1.,
+                timeout_regeneration: difficulty.player_regeneration_timeout,
+                regeneration_amount: difficulty.player_regeneration_amount,
                 timer: None,
             },
             interaction: PlayerCurrentInteraction {
@@ -108,7 +106,8 @@ impl PlayerBundle {
                 child_entity: Entity::from_raw(0),
                 interaction_type: PlayerInteractionType::None,
                 interaction_trigger_at: 0.
-            }
+            },
+            checksum: Checksum::default(),
         }
     }
 }
@@ -121,41 +120,71 @@ pub fn setup_player(
     
     config: &ZombiePlayerInformation,
 
-    index_player: usize
+    index_player: usize,
+    position: Vec2,
 ) {
-    // TODO: for multiplayer
-    // Fetch the location of the player spawner in the map
-    // Use your player index in the player array of the game
-    // to select your color and where your spawn
-
     // get the default weapon for the map
 
     info!("Initializing player {:?}", config.name);
-    let default_weapon_name = zombie_game.starting_weapons.starting_weapon.as_str();
-
-    let weapon = weapons.weapons.iter().find(|w| w.name.eq(default_weapon_name)).unwrap().clone();
+    let starting_weapon = zombie_game.starting_weapons.starting_weapon.as_str();
+
+    // an unknown weapon is reported by the map validation , fallback on the first weapon
+    let weapon = match weapons.weapons.iter().find(|w| w.name.eq(starting_weapon)) {
+        Some(weapon) => Some(weapon.clone()),
+        None => {
+            println!("Unknown starting weapon {}", starting_weapon);
+            weapons.weapons.first().cloned()
+        }
+    };
+    let default_weapon_name = weapon.as_ref().map(|w| w.name.as_str()).unwrap_or("");
 
-    let player = commands.spawn_bundle(PlayerBundle::new(default_weapon_name, config.controller.clone(), index_player, config.is_local)).id();
+    let player = commands.spawn_bundle(PlayerBundle::new(default_weapon_name, config.controller.clone(), index_player, config.is_local, position, &zombie_game.difficulty)).id();
 
     commands.entity(player).insert(Rollback::new(rip.next_id()));
 
-    let weapon = commands.spawn()
-        .insert_bundle(WeaponBundle::new(weapon)).insert(ActiveWeapon{}).id();
+    if let Some(equipment) = &zombie_game.starting_weapons.starting_equipment {
+        match weapons.equipments.iter().find(|e| e.name.eq(equipment.as_str())) {
+            Some(equipment) => {
+                commands.entity(player).insert(LethalEquipment { name: equipment.name.clone(), remaining: equipment.count, max: equipment.count, refilled_round: 0 });
+            },
+            None => println!("Unknown starting equipment {}", equipment),
+        }
+    }
+
+    let melee = zombie_game.starting_weapons.starting_melee.as_str();
+    match weapons.melees.iter().find(|m| m.name.eq(melee)) {
+        Some(melee) => {
+            commands.entity(player).insert(MeleeWeapon::new(melee));
+        },
+        None => println!("Unknown starting melee weapon {}", melee),
+    }
 
-    commands.entity(player).add_child(weapon);
+    commands.entity(player).insert(WeaponSlots { limit: zombie_game.starting_weapons.weapon_carry_limit });
 
-    if let Some(alternate_weapon) = &zombie_game.starting_weapons.starting_alternate_weapon {
-        let weapon = weapons.weapons.iter().find(|w| w.name.eq(alternate_weapon.as_str())).unwrap().clone();
+    if let Some(weapon) = weapon {
         let weapon = commands.spawn()
-            .insert_bundle(WeaponBundle::new(weapon)).insert(Rollback::new(rip.next_id())).id();
+            .insert_bundle(WeaponBundle::new(weapon, zombie_game.difficulty.ammunition_multiplier)).insert(ActiveWeapon{}).insert(WeaponSlot(0))
+            .insert(Rollback::new(rip.next_id())).id();
+
         commands.entity(player).add_child(weapon);
     }
+
+    if let Some(alternate_weapon) = &zombie_game.starting_weapons.starting_alternate_weapon {
+        match weapons.weapons.iter().find(|w| w.name.eq(alternate_weapon.as_str())) {
+            Some(weapon) => {
+                let weapon = commands.spawn()
+                    .insert_bundle(WeaponBundle::new(weapon.clone(), zombie_game.difficulty.ammunition_multiplier)).insert(WeaponSlot(1)).insert(Rollback::new(rip.next_id())).id();
+                commands.entity(player).add_child(weapon);
+            },
+            None => println!("Unknown starting alternate weapon {}", alternate_weapon),
+        }
+    }
 }
 
 
 
 pub fn system_health_player(
-	mut q_player_health: Query<(Entity, &mut Health, &mut HealthRegeneration, &mut CharacterMovementState, &mut AnimationTimer, &mut Transform), (With<Player>)>,
+	mut q_player_health: Query<(Entity, &mut Health, &mut HealthRegeneration, &mut CharacterMovementState, &mut AnimationTimer, &mut Transform, &Player)>,
 
     mut game_state: ResMut<State<GameState>>,
 
@@ -166,7 +195,7 @@ pub fn system_health_player(
     mut ev_player_dead: EventWriter<PlayerDeadEvent>,
 
 ) {
-    for (entity, mut health, mut regeneration, mut character_movement_state, mut timer, mut transform) in q_player_health.iter_mut() {
+    for (entity, mut health, mut regeneration, mut character_movement_state, mut timer, mut transform, player) in q_player_health.iter_mut() {
         match health.get_health_change_state() {
             HealthChangeState::GainHealth => {
                 health.apply_change();
@@ -193,6 +222,52 @@ pub fn system_health_player(
     }
 }
 
+// Bring back the dead players during the pause between the rounds ,
+// on a free spawn away from the zombies
+pub fn system_respawn_players(
+    mut commands: Commands,
+
+    q_zombie_game: Query<&ZombieGame>,
+
+    mut q_dead_player: Query<(Entity, &Player, &mut Health, &mut CharacterMovementState, &mut AnimationTimer, &mut Transform, &Children), With<Death>>,
+    q_alive_player: Query<&Transform, (With<Player>, Without<Death>)>,
+    q_zombie: Query<&Transform, (With<Zombie>, Without<Player>)>,
+    q_player_spawn: Query<(&MapElementPosition, &PlayerSpawn)>,
+    q_active_weapon: Query<&Weapon, With<ActiveWeapon>>,
+) {
+    let zombie_game = q_zombie_game.get_single();
+    if zombie_game.is_err() || zombie_game.unwrap().state != ZombieGameState::RoundInterlude {
+        return;
+    }
+
+    let spawns = sorted_spawns(q_player_spawn.iter());
+    let mut alive_players: Vec<Vec2> = q_alive_player.iter().map(|t| t.translation.truncate()).collect();
+    let zombies: Vec<Vec2> = q_zombie.iter().map(|t| t.translation.truncate()).collect();
+
+    let mut dead_players: Vec<_> = q_dead_player.iter_mut().collect();
+    dead_players.sort_by_key(|(_, player, ..)| player.handle);
+
+    for (entity, player, mut health, mut character_movement_state, mut timer, mut transform, childrens) in dead_players.into_iter() {
+        let position = select_respawn(&spawns, player.handle, &alive_players, &zombies);
+
+        health.current_health = health.max_health;
+        health.tmp_health = health.max_health;
+
+        character_movement_state.state = "standing".to_string();
+        for children in childrens.iter() {
+            if let Ok(weapon) = q_active_weapon.get(*children) {
+                character_movement_state.sub_state = weapon.name.clone();
+                timer.offset = weapon.sprite_sheet_offset;
+            }
+        }
+
+        transform.translation = position.extend(10.);
+        commands.entity(entity).remove::<Death>();
+
+        alive_players.push(position);
+    }
+}
+
 pub fn system_unload_players(
     mut commands: Commands,
     q_player: Query<Entity, With<Player>>
@@ -0,0 +1,97 @@
+use bevy::{prelude::*, math::const_vec2};
+
+use crate::shared::map::{MapElementPosition, PlayerSpawn};
+
+use super::PLAYER_SIZE;
+
+const SPAWN_OFFSETS: [Vec2; 4] = [
+    const_vec2!([-50., 50.]),
+    const_vec2!([50., 50.]),
+    const_vec2!([-50., -50.]),
+    const_vec2!([50., -50.]),
+];
+
+// distance from a player for a spawn to be consider free
+const SPAWN_FREE_DISTANCE: f32 = PLAYER_SIZE.x * 2.;
+
+// Offset around the origin when the map has no spawn , each group of
+// four players is place further away
+pub fn get_spawn_offset(player_index: usize) -> Vec2 {
+    SPAWN_OFFSETS[player_index % SPAWN_OFFSETS.len()] * (1 + player_index / SPAWN_OFFSETS.len()) as f32
+}
+
+// Spawn points of the map sorted by position so every peer select the same one
+pub fn sorted_spawns<'a>(spawns: impl Iterator<Item = (&'a MapElementPosition, &'a PlayerSpawn)>) -> Vec<(Vec2, PlayerSpawn)> {
+    let mut spawns: Vec<(Vec2, PlayerSpawn)> = spawns.map(|(p, s)| (p.position, s.clone())).collect();
+    spawns.sort_by(|a, b| a.0.x.partial_cmp(&b.0.x).unwrap().then(a.0.y.partial_cmp(&b.0.y).unwrap()));
+    spawns
+}
+
+// Select the starting position of a player , when the spawns have a team
+// the players are split between the teams by their index
+pub fn select_player_spawn(spawns: &Vec<(Vec2, PlayerSpawn)>, player_index: usize) -> Vec2 {
+    let nbr_team = spawns.iter().filter_map(|(_, s)| s.team).max().map(|x| x + 1).unwrap_or(1);
+    let team = player_index % nbr_team;
+
+    let candidates: Vec<Vec2> = spawns.iter()
+        .filter(|(_, s)| s.team.is_none() || s.team == Some(team))
+        .map(|(p, _)| *p)
+        .collect();
+
+    if candidates.len() == 0 {
+        return get_spawn_offset(player_index);
+    }
+
+    // more players than spawns , place the extra players around the spawn
+    let index_in_team = player_index / nbr_team;
+    let spawn = candidates[index_in_team % candidates.len()];
+    let round = index_in_team / candidates.len();
+    if round == 0 {
+        spawn
+    } else {
+        spawn + get_spawn_offset(round - 1)
+    }
+}
+
+// Select the spawn for a player coming back to life , the spawn need to be
+// free of other player , in the zone of the alive players if possible and
+// the furthest from the zombies
+pub fn select_respawn(
+    spawns: &Vec<(Vec2, PlayerSpawn)>,
+    player_index: usize,
+    alive_players: &Vec<Vec2>,
+    zombies: &Vec<Vec2>,
+) -> Vec2 {
+    let mut candidates: Vec<&(Vec2, PlayerSpawn)> = spawns.iter()
+        .filter(|(p, _)| !alive_players.iter().any(|a| a.distance(*p) < SPAWN_FREE_DISTANCE))
+        .collect();
+
+    if candidates.len() == 0 {
+        return select_player_spawn(spawns, player_index);
+    }
+
+    let closest_to_players = spawns.iter()
+        .filter(|_| alive_players.len() > 0)
+        .min_by(|a, b| {
+            let distance = |p: &Vec2| alive_players.iter().map(|x| x.distance(*p)).fold(f32::MAX, f32::min);
+            distance(&a.0).partial_cmp(&distance(&b.0)).unwrap()
+        });
+    if let Some((_, PlayerSpawn { zone: Some(zone), .. })) = closest_to_players {
+        if candidates.iter().any(|(_, s)| s.zone.as_ref() == Some(zone)) {
+            candidates = candidates.into_iter().filter(|(_, s)| s.zone.as_ref() == Some(zone)).collect();
+        }
+    }
+
+    let distance_zombie = |p: &Vec2| zombies.iter().map(|z| z.distance(*p)).fold(f32::MAX, f32::min);
+
+    let mut selected = candidates[0].0;
+    let mut selected_distance = distance_zombie(&selected);
+    for (position, _) in candidates.iter().skip(1) {
+        let distance = distance_zombie(position);
+        if distance > selected_distance {
+            selected = *position;
+            selected_distance = distance;
+        }
+    }
+    selected
+}
@@ -8,6 +8,7 @@ pub fn get_cursor_location(
 ) -> Vec2 {
     // get the camera info and transform
     // assuming there is exactly one main camera entity, so query::single() is OK
+    // TODO: resolve against the viewport of the keyboard player once the split-screen exist (see README)
     let (camera, camera_transform) = q_camera.single();
 
     let window = match camera.target {
@@ -50,6 +51,20 @@ pub fn vec2_perpendicular_counter_clockwise(vec: Vec2) -> Vec2 {
     Vec2::new(-vec.y, vec.x)
 }
 
+// Rotate the vector counter clockwise by the angle in radians
+pub fn vec2_rotate(vec: Vec2, angle: f32) -> Vec2 {
+    Vec2::new(vec.x * angle.cos() - vec.y * angle.sin(), vec.x * angle.sin() + vec.y * angle.cos())
+}
+
+// Random number between 0 and 1 , the same on every peer for the same seed
+pub fn deterministic_random(seed: u32) -> f32 {
+    let mut x = seed.wrapping_mul(2654435761) ^ 0x9e3779b9;
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x85ebca6b);
+    x ^= x >> 13;
+    (x % 10000) as f32 / 10000.
+}
+
 #[derive(Default, Reflect, Hash, Component)]
 #[reflect(Hash)]
 pub struct Checksum {
@@ -67,4 +82,15 @@ pub fn fletcher16(data: &[u8]) -> u16 {
     }
 
     (sum2 << 8) | sum1
-}
\ No newline at end of file
+}
+// Same lookup as the bevy asset server for the assets folder
+#[cfg(not(target_arch = "wasm32"))]
+pub fn asset_root() -> std::path::PathBuf {
+    if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        return std::path::PathBuf::from(manifest_dir).join("assets");
+    }
+    std::env::current_exe().ok()
+        .and_then(|x| x.parent().map(|x| x.to_path_buf()))
+        .unwrap_or_default()
+        .join("assets")
+}
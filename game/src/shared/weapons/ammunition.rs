@@ -1,12 +1,165 @@
-use bevy::{prelude::*, sprite::collide_aabb::collide};
+use std::collections::HashSet;
 
-use crate::shared::{character::Velocity, game::GameSpeed, map::MapElementPosition, zombies::zombie::Zombie, collider::ProjectileCollider, player::Player};
+use bevy::{prelude::*, math::const_vec2};
+use bevy_ggrs::{RollbackIdProvider, Rollback};
 
-use super::weapons::{ExpiringComponent, Projectile};
+use crate::shared::{character::Velocity, game::GameSpeed, map::MapElementPosition, zombies::zombie::Zombie, collider::{ProjectileCollider, ray_aabb}, player::Player, health::Health};
+
+use super::{weapons::{ExpiringComponent, Projectile, ProjectileHits, Weapon}, equipment::{Grenade, Explosion}};
+
+// seconds the tracer of a hitscan weapon stay on screen
+const TRACER_DURATION: f32 = 0.08;
+const TRACER_WIDTH: f32 = 2.;
+// size of a projectile for the collisions
+const PROJECTILE_HIT_SIZE: Vec2 = const_vec2!([10., 10.]);
+
+// Elements a projectile or a hitscan ray can hit
+pub type HitscanColliderQuery<'w, 's> = Query<'w, 's,
+    (Entity, &'static Transform, &'static MapElementPosition, Option<&'static Zombie>, Option<&'static mut Health>, Option<&'static Rollback>),
+    (
+        With<ProjectileCollider>,
+        With<MapElementPosition>,
+        Without<Player>,
+    ),
+>;
+
+// Line drawn from the weapon to the end of the ray of a hitscan weapon
+#[derive(Component)]
+pub struct Tracer {}
+
+// Remove the health of a zombie hit by a projectile , and despawn it when it's dead
+pub fn damage_zombie(commands: &mut Commands, hit_entity: Entity, health: Option<Mut<Health>>, damage: f32) -> bool {
+    let dead = match health {
+        Some(mut health) => {
+            health.current_health -= damage;
+            health.tmp_health = health.current_health;
+            health.current_health <= 0.
+        },
+        None => true,
+    };
+    if dead {
+        commands.entity(hit_entity).despawn();
+    }
+    dead
+}
+
+// Hit the colliders crossed by the segment from `origin` , the closest first. Each zombie use
+// one penetration of the projectile and reduce its damage , the zombies already hit are skipped
+// and any other collider stop the projectile. Return the distance where the projectile stop
+pub fn hit_along_segment(
+    commands: &mut Commands,
+    collider_query: &mut HitscanColliderQuery,
+    origin: Vec2,
+    direction: Vec2,
+    length: f32,
+    size: Vec2,
+    projectile: &mut Projectile,
+    projectile_hits: &mut ProjectileHits,
+    killed: &mut HashSet<Entity>,
+) -> Option<f32> {
+    let mut hits: Vec<(f32, Entity)> = collider_query.iter()
+        .filter(|(entity, _, _, _, _, _)| !killed.contains(entity))
+        .filter_map(|(entity, transform, info, _, _, _)| {
+            ray_aabb(origin, direction, transform.translation.truncate(), info.size + size)
+                .filter(|distance| *distance <= length)
+                .map(|distance| (distance, entity))
+        })
+        .collect();
+    hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    for (distance, entity) in hits {
+        if let Ok((hit_entity, _, _, zombie, health, rollback)) = collider_query.get_mut(entity) {
+            if zombie.is_none() {
+                return Some(distance);
+            }
+            let id = rollback.map(|x| x.id());
+            if id.map(|id| projectile_hits.hits.contains(&id)).unwrap_or(false) {
+                continue;
+            }
+            if damage_zombie(commands, hit_entity, health, projectile.damage) {
+                killed.insert(hit_entity);
+            }
+            projectile_hits.hits.extend(id);
+            projectile.damage *= 1. - projectile.damage_loss.clamp(0., 1.);
+            projectile.penetration = projectile.penetration.saturating_sub(1);
+            if projectile.penetration == 0 {
+                return Some(distance);
+            }
+        }
+    }
+    None
+}
+
+// Cast a ray from the weapon , the zombies on the way are hit up to the penetration
+// of the weapon and any other collider stop the ray
+pub fn fire_hitscan(
+    commands: &mut Commands,
+    rip: &mut ResMut<RollbackIdProvider>,
+    weapon: &Weapon,
+    time: &Res<Time>,
+    starting_point: &Vec3,
+    offset_each: &Vec2,
+    direction: &Vec2,
+    index: u32,
+    collider_query: &mut HitscanColliderQuery,
+) {
+    let origin = starting_point.truncate() + *offset_each * index as f32;
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    let end = hit_along_segment(
+        commands,
+        collider_query,
+        origin,
+        direction,
+        weapon.range,
+        Vec2::ZERO,
+        &mut Projectile::new(weapon),
+        &mut ProjectileHits::default(),
+        &mut HashSet::new(),
+    ).unwrap_or(weapon.range);
+
+    commands
+        .spawn()
+        .insert(Tracer {})
+        .insert_bundle(SpriteBundle {
+            transform: Transform {
+                translation: (origin + direction * end / 2.).extend(starting_point.z),
+                rotation: Quat::from_rotation_z(direction.y.atan2(direction.x)),
+                ..Transform::default()
+            },
+            sprite: Sprite {
+                color: Color::rgba(1., 0.9, 0.6, 0.8),
+                custom_size: Some(Vec2::new(end, TRACER_WIDTH)),
+                ..Sprite::default()
+            },
+            ..SpriteBundle::default()
+        })
+        .insert(ExpiringComponent {
+            created_at: time.time_since_startup().as_secs_f32(),
+            duration: TRACER_DURATION,
+        })
+        .insert(Rollback::new(rip.next_id()));
+}
+
+// Remove the tracers and the explosions at the end of their duration
+pub fn system_expire_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    query: Query<(Entity, &ExpiringComponent), Or<(With<Tracer>, With<Explosion>)>>,
+) {
+    for (entity, expiring) in query.iter() {
+        if expiring.created_at + expiring.duration <= time.time_since_startup().as_secs_f32() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
 
 pub fn apply_velocity(
     mut commands: Commands,
-    mut query: Query<(&mut Transform, &Velocity, Entity), Without<Player>>,
+    mut query: Query<(&mut Transform, &Velocity, Entity), (Without<Player>, Without<Grenade>)>,
 
     game_speed: Res<GameSpeed>
 ) {
@@ -22,40 +175,44 @@ pub fn apply_velocity(
     }
 }
 
+// The projectiles are already moved by `apply_velocity` , the segment travelled this frame
+// is tested against the colliders and they are hit from the closest one
 pub fn movement_projectile(
     mut commands: Commands,
     time: Res<Time>,
-    projectile_query: Query<(Entity, &Transform, &ExpiringComponent), With<Projectile>>,
-    collider_query: Query<
-        (Entity, &Transform, &MapElementPosition, Option<&Zombie>),
-        (
-            With<ProjectileCollider>,
-            With<MapElementPosition>,
-            Without<Player>,
-        ),
-    >,
+    game_speed: Res<GameSpeed>,
+    mut projectile_query: Query<(Entity, &Transform, &Velocity, &ExpiringComponent, &mut Projectile, &mut ProjectileHits, &Rollback)>,
+    mut collider_query: HitscanColliderQuery,
 ) {
-    let mut i = 0;
-    'outer: for (projectile_entity, transform, expiring) in projectile_query.iter() {
-        i += 1;
+    // sort to resolve the projectiles in the same order on every peer
+    let mut projectiles: Vec<_> = projectile_query.iter_mut().collect();
+    projectiles.sort_by_key(|x| x.6.id());
+
+    // zombies killed this frame , the next projectiles go through them
+    let mut killed: HashSet<Entity> = HashSet::new();
+
+    for (projectile_entity, transform, velocity, expiring, mut projectile, mut projectile_hits, _) in projectiles {
         if expiring.created_at + expiring.duration <= time.time_since_startup().as_secs_f32() {
             commands.entity(projectile_entity).despawn();
-            break;
+            continue;
         }
-        for (hit_entity, transform_collider, info, zombie) in collider_query.iter() {
-            let collision = collide(
-               transform_collider.translation,
-                info.size,
-                transform.translation,
-                Vec2::new(10., 10.),
-            );
-            if collision.is_some() {
-                if let Some(_zombie) = zombie {
-                    commands.entity(hit_entity).despawn();
-                }
-                commands.entity(projectile_entity).despawn();
-                break 'outer;
-            }
+
+        let end = transform.translation.truncate();
+        let start = end - velocity.v * game_speed.0;
+
+        let stopped = hit_along_segment(
+            &mut commands,
+            &mut collider_query,
+            start,
+            (end - start).normalize_or_zero(),
+            start.distance(end),
+            PROJECTILE_HIT_SIZE,
+            &mut projectile,
+            &mut projectile_hits,
+            &mut killed,
+        );
+        if stopped.is_some() {
+            commands.entity(projectile_entity).despawn();
         }
     }
 }
@@ -0,0 +1,275 @@
+use bevy::{prelude::*, math::const_vec2, reflect::TypeUuid, asset::{AssetLoader, LoadContext, BoxedFuture, LoadedAsset}};
+use bevy_ggrs::{RollbackIdProvider, Rollback};
+use ggrs::InputStatus;
+use serde::Deserialize;
+
+use crate::shared::{
+    character::{Velocity, LookingAt, Death},
+    game::{GameSpeed, ZombieGame, ZombieGameState},
+    health::Health,
+    map::{MapElementPosition, Wall},
+    player::{Player, input::{BoxInput, INPUT_THROW_EQUIPMENT}},
+    zombies::zombie::Zombie,
+};
+
+use super::{ammunition::damage_zombie, weapons::ExpiringComponent, melee::Melee};
+
+const GRENADE_SIZE: Vec2 = const_vec2!([8., 8.]);
+// part of the speed kept when a grenade bounce on a wall
+const GRENADE_RESTITUTION: f32 = 0.6;
+// seconds the explosion stay on screen
+const EXPLOSION_DURATION: f32 = 0.15;
+
+fn default_friction() -> f32 {
+    0.97
+}
+
+// Stats of a lethal equipment , loaded from `weapons/default.equipment.ron`
+#[derive(Default, Clone, Deserialize)]
+pub struct Equipment {
+    pub name: String,
+    // equipment given at the start and at the end of each round
+    pub count: u32,
+    // seconds before the explosion
+    pub fuse: f32,
+    pub throw_speed: f32,
+    // part of the speed kept each frame
+    #[serde(default = "default_friction")]
+    pub friction: f32,
+    pub radius: f32,
+    // damage at the center of the explosion , reduced with the distance up to the radius
+    pub damage: f32,
+    // damage to the players , 0 to only hurt the zombies
+    #[serde(default)]
+    pub player_damage: f32,
+}
+
+#[derive(Deserialize, TypeUuid, Default)]
+#[uuid = "39cadc56-aa9c-4543-8640-a018b74b5031"]
+pub struct EquipmentAsset {
+    pub equipments: Vec<Equipment>,
+    #[serde(default)]
+    pub melees: Vec<Melee>,
+}
+
+#[derive(Default)]
+pub struct EquipmentAssetLoader;
+
+impl AssetLoader for EquipmentAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let equipment_asset = ron::de::from_bytes::<EquipmentAsset>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(equipment_asset));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["equipment.ron"]
+    }
+}
+
+// Lethal equipment slot of a player , separated from the weapons
+#[derive(Default, Component, Reflect)]
+pub struct LethalEquipment {
+    pub name: String,
+    pub remaining: u32,
+    pub max: u32,
+    // last round at the end of which the equipment was refilled
+    pub refilled_round: i32,
+}
+
+#[derive(Default, Component, Reflect)]
+pub struct Grenade {
+    pub damage: f32,
+    pub player_damage: f32,
+    pub radius: f32,
+    pub friction: f32,
+    // frames before the explosion
+    pub fuse: u32,
+}
+
+#[derive(Component)]
+pub struct Explosion {
+    pub radius: f32,
+    // rollback id of the grenade , the same when the explosion is simulated again by a rollback
+    pub grenade: u32,
+}
+
+pub fn system_throw_equipment(
+    mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
+    game_speed: Res<GameSpeed>,
+    equipments: Res<Assets<EquipmentAsset>>,
+    weapons: Res<super::loader::WeaponAssetState>,
+    inputs: Res<Vec<(BoxInput, InputStatus)>>,
+
+    mut q_player: Query<(&Transform, &LookingAt, &Player, &mut LethalEquipment), Without<Death>>,
+) {
+    let asset = match equipments.get(&weapons.equipment_handle) {
+        Some(asset) => asset,
+        None => return,
+    };
+
+    for (transform, looking_at, player, mut equipment) in q_player.iter_mut() {
+        if inputs.len() <= player.handle {
+            continue;
+        }
+
+        let box_input = match inputs[player.handle].1 {
+            InputStatus::Disconnected => BoxInput::default(),
+            _ => inputs[player.handle].0,
+        };
+
+        if box_input.inp & INPUT_THROW_EQUIPMENT != INPUT_THROW_EQUIPMENT || equipment.remaining == 0 {
+            continue;
+        }
+
+        let config = match asset.equipments.iter().find(|x| x.name == equipment.name) {
+            Some(config) => config,
+            None => continue,
+        };
+
+        let position = transform.translation.truncate();
+        let direction = if !looking_at.1 { looking_at.0 - position } else { looking_at.0 }.normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+
+        equipment.remaining -= 1;
+
+        commands
+            .spawn()
+            .insert(Grenade {
+                damage: config.damage,
+                player_damage: config.player_damage,
+                radius: config.radius,
+                friction: config.friction,
+                fuse: (config.fuse / game_speed.0).round() as u32,
+            })
+            .insert_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: transform.translation,
+                    ..Transform::default()
+                },
+                sprite: Sprite {
+                    color: Color::DARK_GREEN,
+                    custom_size: Some(GRENADE_SIZE),
+                    ..Sprite::default()
+                },
+                ..SpriteBundle::default()
+            })
+            .insert(Velocity { v: direction * config.throw_speed })
+            .insert(Rollback::new(rip.next_id()));
+    }
+}
+
+fn hit_wall(position: Vec2, q_wall: &Query<(&Transform, &MapElementPosition), (With<Wall>, Without<Grenade>)>) -> bool {
+    q_wall.iter().any(|(transform, info)| {
+        let distance = (transform.translation.truncate() - position).abs();
+        let size = (info.size + GRENADE_SIZE) / 2.;
+        distance.x < size.x && distance.y < size.y
+    })
+}
+
+// Move the grenades , they bounce on the walls and explode at the end of the fuse
+// , the damage decrease with the distance to the explosion
+pub fn system_grenade(
+    mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
+    game_speed: Res<GameSpeed>,
+    time: Res<Time>,
+
+    mut q_grenade: Query<(Entity, &mut Transform, &mut Velocity, &mut Grenade, &Rollback)>,
+    q_wall: Query<(&Transform, &MapElementPosition), (With<Wall>, Without<Grenade>)>,
+    mut q_zombie: Query<(Entity, &Transform, Option<&mut Health>), (With<Zombie>, Without<Grenade>)>,
+    mut q_player: Query<(&Transform, &mut Health), (With<Player>, Without<Death>, Without<Zombie>, Without<Grenade>)>,
+) {
+    for (entity, mut transform, mut velocity, mut grenade, rollback) in q_grenade.iter_mut() {
+        let position = transform.translation.truncate();
+        let movement = velocity.v * game_speed.0;
+
+        // the axis are tested one after the other to bounce on the side of the wall hit
+        let mut next = position;
+        if hit_wall(next + Vec2::new(movement.x, 0.), &q_wall) {
+            velocity.v.x = -velocity.v.x * GRENADE_RESTITUTION;
+        } else {
+            next.x += movement.x;
+        }
+        if hit_wall(next + Vec2::new(0., movement.y), &q_wall) {
+            velocity.v.y = -velocity.v.y * GRENADE_RESTITUTION;
+        } else {
+            next.y += movement.y;
+        }
+        transform.translation = next.extend(transform.translation.z);
+        velocity.v *= grenade.friction;
+
+        if grenade.fuse > 0 {
+            grenade.fuse -= 1;
+            continue;
+        }
+
+        for (zombie_entity, zombie_transform, health) in q_zombie.iter_mut() {
+            let distance = zombie_transform.translation.truncate().distance(next);
+            if distance < grenade.radius {
+                damage_zombie(&mut commands, zombie_entity, health, grenade.damage * (1. - distance / grenade.radius));
+            }
+        }
+
+        if grenade.player_damage > 0. {
+            for (player_transform, mut health) in q_player.iter_mut() {
+                let distance = player_transform.translation.truncate().distance(next);
+                if distance < grenade.radius {
+                    health.tmp_health -= grenade.player_damage * (1. - distance / grenade.radius);
+                }
+            }
+        }
+
+        commands.entity(entity).despawn();
+        commands
+            .spawn()
+            .insert(Explosion { radius: grenade.radius, grenade: rollback.id() })
+            .insert_bundle(SpriteBundle {
+                transform: Transform {
+                    translation: transform.translation,
+                    ..Transform::default()
+                },
+                sprite: Sprite {
+                    color: Color::rgba(1., 0.6, 0.1, 0.6),
+                    custom_size: Some(Vec2::splat(grenade.radius * 2.)),
+                    ..Sprite::default()
+                },
+                ..SpriteBundle::default()
+            })
+            .insert(ExpiringComponent {
+                created_at: time.time_since_startup().as_secs_f32(),
+                duration: EXPLOSION_DURATION,
+            })
+            .insert(Rollback::new(rip.next_id()));
+    }
+}
+
+// The players get all their equipment back at the end of a round , the round
+// refilled is kept in the equipment so the refill is part of the rollback state
+pub fn system_refill_equipment(
+    q_zombie_game: Query<&ZombieGame>,
+    mut q_equipment: Query<&mut LethalEquipment>,
+) {
+    let zombie_game = match q_zombie_game.get_single() {
+        Ok(zombie_game) => zombie_game,
+        Err(_) => return,
+    };
+    if zombie_game.state != ZombieGameState::RoundInterlude {
+        return;
+    }
+    for mut equipment in q_equipment.iter_mut() {
+        if equipment.refilled_round < zombie_game.round {
+            equipment.remaining = equipment.max;
+            equipment.refilled_round = zombie_game.round;
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use std::rc::Weak;
+use std::{rc::Weak, sync::{Arc, Mutex}};
 
 use bevy::{prelude::*, utils::HashMap, asset::{AssetLoader, LoadContext, BoxedFuture, LoadedAsset}, reflect::TypeUuid};
 use serde::Deserialize;
 
-use super::weapons::{Weapon, AmmunitionState, WeaponState};
+use crate::shared::animation::AnimationTimer;
+
+use super::{weapons::{Weapon, AmmunitionState, ActiveWeapon}, equipment::{Equipment, EquipmentAsset, EquipmentAssetLoader}, melee::Melee};
 
 
 #[derive(Deserialize, TypeUuid, Default, Component)]
@@ -16,12 +18,31 @@ pub struct WeaponsAsset {
 #[derive(Default)]
 pub struct WeaponAssetState {
     pub handle: Handle<WeaponsAsset>,
+    pub equipment_handle: Handle<EquipmentAsset>,
     pub loaded: bool,
-    pub weapons: Vec<Weapon>
+    pub weapons: Vec<Weapon>,
+    pub equipments: Vec<Equipment>,
+    pub melees: Vec<Melee>,
+}
+
+impl WeaponAssetState {
+    // Upgraded variant of a weapon , `None` if it can't be upgraded
+    pub fn upgrade_of(&self, name: &str) -> Option<&Weapon> {
+        self.weapons.iter().find(|w| w.upgrade_of.as_deref() == Some(name))
+    }
 }
 
+// Error of the last loading of `weapons.ron` , shared with the loader that run outside of the systems
+#[derive(Default, Clone)]
+pub struct WeaponAssetError(pub Arc<Mutex<Option<String>>>);
+
+#[derive(Component)]
+pub struct WeaponAssetErrorText {}
+
 #[derive(Default)]
-pub struct WeaponAssetLoader;
+pub struct WeaponAssetLoader {
+    error: WeaponAssetError,
+}
 
 impl AssetLoader for WeaponAssetLoader {
     fn load<'a>(
@@ -30,9 +51,18 @@ impl AssetLoader for WeaponAssetLoader {
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
         Box::pin(async move {
-            let map_data_asset = ron::de::from_bytes::<WeaponsAsset>(bytes)?;
-            load_context.set_default_asset(LoadedAsset::new(map_data_asset));
-            Ok(())
+            match ron::de::from_bytes::<WeaponsAsset>(bytes) {
+                Ok(weapons_asset) => {
+                    *self.error.0.lock().unwrap() = None;
+                    load_context.set_default_asset(LoadedAsset::new(weapons_asset));
+                    Ok(())
+                },
+                Err(err) => {
+                    // the previous weapons are kept until the file is fixed
+                    *self.error.0.lock().unwrap() = Some(format!("Can't load {:?} : {}", load_context.path(), err));
+                    Err(err.into())
+                },
+            }
         })
     }
 
@@ -48,18 +78,23 @@ pub fn setup_weapons_asset(
 ) {
     let handle: Handle<WeaponsAsset> = asset_server.load("weapons/weapons.ron");
     state.handle = handle;
+    state.equipment_handle = asset_server.load("weapons/default.equipment.ron");
     state.loaded = false;
 }
 
 pub fn system_weapon_asset(
     mut state: ResMut<WeaponAssetState>,
-    custom_assets: ResMut<Assets<WeaponsAsset>>
+    custom_assets: ResMut<Assets<WeaponsAsset>>,
+    equipment_assets: Res<Assets<EquipmentAsset>>,
 ) {
 	if !state.loaded {
 		let v = custom_assets.get(&state.handle);
-		if v.is_some() {
+		let e = equipment_assets.get(&state.equipment_handle);
+		if v.is_some() && e.is_some() {
 			state.loaded = true;
-            state.weapons = v.unwrap().weapons.iter().map(|x| x.clone()).collect()
+            state.weapons = v.unwrap().weapons.iter().map(|x| x.clone()).collect();
+            state.equipments = e.unwrap().equipments.clone();
+            state.melees = e.unwrap().melees.clone();
 		}
 	}
 }
@@ -69,10 +104,14 @@ pub struct WeaponAssetPlugin {}
 
 impl Plugin for WeaponAssetPlugin {
 	fn build(&self, app: &mut App) {
+		let error = WeaponAssetError::default();
 		app
 			.init_resource::<WeaponAssetState>()
+			.insert_resource(error.clone())
 			.add_asset::<WeaponsAsset>()
-			.init_asset_loader::<WeaponAssetLoader>()
+			.add_asset_loader(WeaponAssetLoader { error })
+			.add_asset::<EquipmentAsset>()
+			.init_asset_loader::<EquipmentAssetLoader>()
 
             .add_startup_system(setup_weapons_asset)
             .add_system(system_weapon_asset)
@@ -80,31 +119,95 @@ impl Plugin for WeaponAssetPlugin {
 	}
 }
 
+// Show the errors of `weapons.ron` on screen , only for the windowed app as
+// the `WeaponAssetPlugin` also run headless
+pub struct WeaponAssetErrorUiPlugin {}
+
+impl Plugin for WeaponAssetErrorUiPlugin {
+	fn build(&self, app: &mut App) {
+		app
+			.add_startup_system(setup_weapon_asset_error_ui)
+			.add_system(system_weapon_asset_error_ui);
+	}
+}
+
+// Apply the new stats of `weapons.ron` to the weapons of the players , the ammunition
+// of each weapon is kept up to the new limits
 pub fn react_weapon_asset_change(
     mut asset_events: EventReader<AssetEvent<WeaponsAsset>>,
-    custom_assets: ResMut<Assets<WeaponsAsset>>,
-	mut query_player_weapon: Query<(&mut AmmunitionState, &mut WeaponState, &mut Weapon, &Parent), With<WeaponState>>,
+    custom_assets: Res<Assets<WeaponsAsset>>,
+    mut state: ResMut<WeaponAssetState>,
+	mut query_player_weapon: Query<(&mut AmmunitionState, &mut Weapon, &Parent, Option<&ActiveWeapon>)>,
+    mut query_timer: Query<&mut AnimationTimer>,
 ) {
     for event in asset_events.iter() {
         match event {
             AssetEvent::Modified { handle } => {
-                let asset = custom_assets.get(handle).unwrap();
-                for (mut ammo_state, _, mut weapon, parent) in query_player_weapon.iter_mut() {
-                    let new_config = asset.weapons.iter().find(|&x| x.name.eq(weapon.name.as_str()));
-                    if let Some(new_config) = new_config {
-                        weapon.automatic = new_config.automatic;
-                        weapon.ammunition = new_config.ammunition.clone();
-                        weapon.asset_name = new_config.asset_name.clone();
-                        weapon.firing_rate = new_config.firing_rate;
-                        weapon.offset = new_config.offset;
-                        weapon.reloading_time = new_config.reloading_time;
-
-                        ammo_state.remaining_ammunition = new_config.ammunition.magasin_nbr_starting * new_config.ammunition.magasin_size;
+                let asset = match custom_assets.get(handle) {
+                    Some(asset) => asset,
+                    None => continue,
+                };
+                state.weapons = asset.weapons.clone();
+
+                for (mut ammo_state, mut weapon, parent, active) in query_player_weapon.iter_mut() {
+                    let new_config = match asset.weapons.iter().find(|&x| x.name.eq(weapon.name.as_str())) {
+                        Some(new_config) => new_config,
+                        None => {
+                            println!("The weapon {} is no longer in weapons.ron , its stats are kept", weapon.name);
+                            continue;
+                        }
+                    };
+                    *weapon = new_config.clone();
+                    ammo_state.clamp_to_limit(&weapon.ammunition);
+
+                    if active.is_some() {
+                        if let Ok(mut timer) = query_timer.get_mut(parent.0) {
+                            timer.offset = weapon.sprite_sheet_offset;
+                        }
                     }
                 }
             },
             _ => {}
         }
     }
+}
 
+pub fn setup_weapon_asset_error_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn().insert(WeaponAssetErrorText{}).insert_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(5.),
+                left: Val::Px(5.),
+                ..default()
+            },
+            ..default()
+        },
+        text: Text::with_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 18.0,
+                color: Color::rgb(1., 0.2, 0.2),
+            },
+            default(),
+        ),
+        ..default()
+    });
+}
+
+// Show the error of `weapons.ron` on screen until the file is fixed
+pub fn system_weapon_asset_error_ui(
+    error: Res<WeaponAssetError>,
+    mut q_text: Query<&mut Text, With<WeaponAssetErrorText>>,
+) {
+    let value = error.0.lock().unwrap().clone().unwrap_or_default();
+    for mut text in q_text.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
 }
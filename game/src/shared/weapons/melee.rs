@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use ggrs::InputStatus;
+use serde::Deserialize;
+
+use crate::shared::{
+    character::{LookingAt, Death, CharacterMovementState},
+    game::GameSpeed,
+    health::Health,
+    map::{MapElementPosition, Wall},
+    player::{Player, input::{BoxInput, INPUT_MELEE}},
+    zombies::zombie::{Zombie, BotDestination, ZOMBIE_SIZE},
+};
+
+use super::{ammunition::damage_zombie, loader::WeaponAssetState};
+
+fn default_melee_duration() -> f32 {
+    0.3
+}
+
+// Stats of a melee weapon , loaded from the `melees` of `weapons/default.equipment.ron`
+#[derive(Default, Clone, Deserialize)]
+pub struct Melee {
+    pub name: String,
+    pub damage: f32,
+    // reach of the attack from the center of the player
+    pub range: f32,
+    // half angle of the cone in front of the player , in degrees
+    pub angle: f32,
+    // distance the zombies hit are pushed back
+    #[serde(default)]
+    pub knockback: f32,
+    // seconds between two attacks
+    pub cooldown: f32,
+    // seconds the melee animation is played
+    #[serde(default = "default_melee_duration")]
+    pub duration: f32,
+}
+
+// Melee weapon of a player , always available in addition of the weapons
+#[derive(Default, Component, Reflect)]
+pub struct MeleeWeapon {
+    pub name: String,
+    pub damage: f32,
+    pub range: f32,
+    pub angle: f32,
+    pub knockback: f32,
+    // seconds between two attacks
+    pub cooldown: f32,
+    // seconds the melee animation is played
+    pub duration: f32,
+    // frames before the next attack
+    pub cooldown_remaining: u32,
+    // frames before the end of the melee animation
+    pub attack_remaining: u32,
+}
+
+impl MeleeWeapon {
+    pub fn new(config: &Melee) -> Self {
+        let mut melee = MeleeWeapon::default();
+        melee.upgrade(config);
+        melee
+    }
+
+    // Replace the stats by the ones of an other melee weapon , the cooldown in progress is kept
+    pub fn upgrade(&mut self, config: &Melee) {
+        self.name = config.name.clone();
+        self.damage = config.damage;
+        self.range = config.range;
+        self.angle = config.angle;
+        self.knockback = config.knockback;
+        self.cooldown = config.cooldown;
+        self.duration = config.duration;
+    }
+}
+
+// Melee weapon given to a player , inserted on the player by the rollback systems of the
+// perks and the wall-buys so the upgrade is part of the rollback state , removed once applied
+#[derive(Default, Component, Reflect)]
+pub struct MeleeUpgrade {
+    pub name: String,
+}
+
+// Attack the zombies in a cone in front of the player , they are damaged and pushed back
+// , the attack play the `melee` animation of the player
+pub fn system_melee(
+    mut commands: Commands,
+    game_speed: Res<GameSpeed>,
+    inputs: Res<Vec<(BoxInput, InputStatus)>>,
+
+    mut q_player: Query<(&Transform, &LookingAt, &Player, &mut MeleeWeapon, &mut CharacterMovementState), Without<Death>>,
+    mut q_zombie: Query<(Entity, &mut Transform, &mut BotDestination, Option<&mut Health>), (With<Zombie>, Without<Player>)>,
+    q_wall: Query<(&Transform, &MapElementPosition), (With<Wall>, Without<Zombie>)>,
+) {
+    for (transform, looking_at, player, mut melee, mut movement_state) in q_player.iter_mut() {
+        if melee.cooldown_remaining > 0 {
+            melee.cooldown_remaining -= 1;
+        }
+        if melee.attack_remaining > 0 {
+            melee.attack_remaining -= 1;
+            // keep the animation over the walking state set by the movement
+            movement_state.state = if melee.attack_remaining > 0 { "melee" } else { "standing" }.to_string();
+        }
+
+        if inputs.len() <= player.handle {
+            continue;
+        }
+
+        let box_input = match inputs[player.handle].1 {
+            InputStatus::Disconnected => BoxInput::default(),
+            _ => inputs[player.handle].0,
+        };
+
+        if box_input.inp & INPUT_MELEE != INPUT_MELEE || melee.cooldown_remaining > 0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let direction = if !looking_at.1 { looking_at.0 - position } else { looking_at.0 }.normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+
+        melee.cooldown_remaining = (melee.cooldown / game_speed.0).round() as u32;
+        melee.attack_remaining = (melee.duration / game_speed.0).round() as u32;
+        movement_state.state = "melee".to_string();
+
+        let reach = melee.range + ZOMBIE_SIZE.x / 2.;
+        let angle = melee.angle.to_radians();
+        for (entity, mut zombie_transform, mut destination, health) in q_zombie.iter_mut() {
+            let zombie_position = zombie_transform.translation.truncate();
+            let to_zombie = zombie_position - position;
+            let distance = to_zombie.length();
+            if distance > reach || (distance > 0. && direction.angle_between(to_zombie).abs() > angle) {
+                continue;
+            }
+
+            if damage_zombie(&mut commands, entity, health, melee.damage) || melee.knockback <= 0. {
+                continue;
+            }
+
+            let pushed = zombie_position + to_zombie.normalize_or_zero() * melee.knockback;
+            let blocked = q_wall.iter().any(|(wall_transform, info)| {
+                let distance = (wall_transform.translation.truncate() - pushed).abs();
+                let size = (info.size + ZOMBIE_SIZE) / 2.;
+                distance.x < size.x && distance.y < size.y
+            });
+            if blocked {
+                continue;
+            }
+
+            zombie_transform.translation = pushed.extend(zombie_transform.translation.z);
+            // the path start from the old position , compute it again from the new one
+            let (goal, target) = (destination.destination, destination.entity);
+            destination.set_destination(goal, pushed, target, 0.);
+        }
+    }
+}
+
+pub fn system_melee_upgrade(
+    mut commands: Commands,
+    weapons: Res<WeaponAssetState>,
+    mut q_melee: Query<(Entity, &MeleeUpgrade, &mut MeleeWeapon)>,
+) {
+    for (entity, upgrade, mut melee) in q_melee.iter_mut() {
+        commands.entity(entity).remove::<MeleeUpgrade>();
+        match weapons.melees.iter().find(|m| m.name.eq(upgrade.name.as_str())) {
+            Some(config) => melee.upgrade(config),
+            None => println!("Unknown melee weapon {}", upgrade.name),
+        }
+    }
+}
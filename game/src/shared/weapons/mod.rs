@@ -2,4 +2,6 @@
 
 pub mod weapons;
 pub mod loader;
-pub mod ammunition;
\ No newline at end of file
+pub mod ammunition;
+pub mod equipment;
+pub mod melee;
@@ -3,22 +3,53 @@ use bevy_ggrs::{RollbackIdProvider, Rollback};
 use ggrs::InputStatus;
 use serde::Deserialize;
 
-use rand::prelude::SliceRandom;
+use super::ammunition::{HitscanColliderQuery, fire_hitscan};
 
 use crate::shared::{
-    utils::{get_cursor_location, vec2_perpendicular_counter_clockwise, vec2_perpendicular_clockwise},
-    collider::ProjectileCollider,
-    animation::AnimationTimer, player::{MainCamera, Player, input::{PlayerCurrentInput, SupportedController, INPUT_FIRE, INPUT_JUST_FIRE, BoxInput, INPUT_WEAPON_CHANGED, INPUT_WEAPON_RELOAD}}, character::{CharacterMovementState, Velocity, LookingAt, Death}
+    utils::{get_cursor_location, Checksum, vec2_perpendicular_counter_clockwise, vec2_perpendicular_clockwise, vec2_rotate, deterministic_random},
+    collider::ProjectileCollider, game::{ZombieGameConfig, GameSpeed},
+    animation::AnimationTimer, player::{MainCamera, Player, input::{PlayerCurrentInput, SupportedController, FrameCount, INPUT_FIRE, INPUT_JUST_FIRE, BoxInput, INPUT_WEAPON_CHANGED, INPUT_WEAPON_RELOAD, INPUT_WEAPON_PREVIOUS, INPUT_WEAPON_SLOT_MASK, INPUT_WEAPON_SLOT_SHIFT}}, character::{CharacterMovementState, Velocity, LookingAt, Death}
 };
 
+use super::loader::WeaponAssetState;
+
 
 fn default_firing_ammunition() -> u32 {
     1
 }
 
+fn default_camera_shake() -> f32 {
+    0.1
+}
+
+fn default_damage() -> f32 {
+    1.
+}
+
+fn default_penetration() -> u32 {
+    1
+}
+
+fn default_hitscan_range() -> f32 {
+    1500.
+}
+
+fn default_pellet_spread() -> f32 {
+    17.
+}
+
+fn default_max_recoil() -> f32 {
+    15.
+}
+
+fn default_ammo_color() -> Vec3 {
+    Vec3::new(1., 0.89, 0.77)
+}
+
 fn default_ammo_sprite_config() -> AmmunitionSpriteConfig {
     AmmunitionSpriteConfig { 
-        size: Vec2::new(5., 5.)
+        size: Vec2::new(5., 5.),
+        color: default_ammo_color(),
     }
 }
 
@@ -29,13 +60,16 @@ pub struct ExpiringComponent {
 }
 
 
-#[derive(Default, Clone, Deserialize)]
+#[derive(Default, Clone, Deserialize, Reflect)]
 pub struct AmmunitionSpriteConfig {
-    //pub color: Vec3,
-    pub size: Vec2
+    pub size: Vec2,
+    // rgb of the projectiles
+    #[serde(default = "default_ammo_color")]
+    pub color: Vec3,
 }
 
-#[derive(Default, Component, Clone, Deserialize)]
+// Stats of a weapon , part of the rollback state because the upgrade stations replace them
+#[derive(Default, Component, Clone, Deserialize, Reflect)]
 pub struct Weapon {
 	pub name: String,
 	pub asset_name: String,
@@ -46,13 +80,73 @@ pub struct Weapon {
     pub firing_ammunition: u32,
     #[serde(default = "default_firing_ammunition")]
     pub spreading_ammunition: u32,
-    pub offset: i32,
 	pub automatic: bool,
 
-    pub sprite_sheet_offset: usize
+    pub sprite_sheet_offset: usize,
+
+    // trauma added to the camera of the local player when firing
+    #[serde(default = "default_camera_shake")]
+    pub camera_shake: f32,
+
+    // health removed from a zombie by each projectile
+    #[serde(default = "default_damage")]
+    pub damage: f32,
+
+    // the weapon cast a ray instead of firing projectiles , like a sniper or a laser
+    #[serde(default)]
+    pub hitscan: bool,
+    // zombies hit by a projectile or the ray before it stop
+    #[serde(default = "default_penetration")]
+    pub penetration: u32,
+    // part of the damage lost each time a zombie is went through
+    #[serde(default)]
+    pub penetration_damage_loss: f32,
+    #[serde(default = "default_hitscan_range")]
+    pub range: f32,
+
+    // name of the weapon this one is the upgraded variant of , given by the upgrade stations
+    #[serde(default)]
+    pub upgrade_of: Option<String>,
+
+    // cone in degrees where each shot deviate randomly , when standing and when moving
+    #[serde(default)]
+    pub spread: f32,
+    #[serde(default)]
+    pub moving_spread: Option<f32>,
+    // cone in degrees where the `spreading_ammunition` pellets of a shot are spread evenly
+    #[serde(default = "default_pellet_spread")]
+    pub pellet_spread: f32,
+    // degrees added to the spread by each shot up to `max_recoil` , and recovered each second
+    #[serde(default)]
+    pub recoil: f32,
+    #[serde(default = "default_max_recoil")]
+    pub max_recoil: f32,
+    #[serde(default)]
+    pub recoil_recovery: f32,
+}
+
+impl Weapon {
+    // Cone in degrees where the next shot can go
+    pub fn accuracy(&self, moving: bool, recoil: &WeaponRecoil) -> f32 {
+        let spread = if moving { self.moving_spread.unwrap_or(self.spread) } else { self.spread };
+        spread + recoil.current
+    }
+
+    // Direction of each pellet of a shot , the shot deviate in the accuracy cone from the `random`
+    // between 0 and 1 and the pellets are spread evenly around it
+    pub fn pellet_directions(&self, direction: Vec2, accuracy: f32, random: f32) -> Vec<Vec2> {
+        let deviation = (random - 0.5) * accuracy.to_radians();
+        let pellets = self.spreading_ammunition.max(1);
+        (0..pellets).map(|i| {
+            let fan = if pellets > 1 {
+                (i as f32 / (pellets - 1) as f32 - 0.5) * self.pellet_spread.to_radians()
+            } else { 0. };
+            vec2_rotate(direction, deviation + fan)
+        }).collect()
+    }
 }
 
-#[derive(Default, Clone, Deserialize)]
+#[derive(Default, Clone, Deserialize, Reflect)]
 pub struct Ammunition {
 	pub magasin_size: i32,
 	pub magasin_limit: i32,
@@ -64,12 +158,60 @@ pub struct Ammunition {
     pub sprite_config: AmmunitionSpriteConfig
 }
 
+impl Ammunition {
+	// maximum of ammunition kept outside the magasin
+	pub fn reserve_limit(&self) -> i32 {
+		self.magasin_limit * self.magasin_size
+	}
+}
+
+// Degrees added to the spread of a weapon by the last shots
+#[derive(Default, Component, Reflect)]
+pub struct WeaponRecoil {
+	pub current: f32,
+}
+
 #[derive(Default, Component, Reflect)]
 pub struct AmmunitionState {
 	pub mag_remaining: i32,
 	pub remaining_ammunition: i32
 }
 
+impl AmmunitionState {
+	// fill the magasin and the reserve up to the limit of the weapon , the max ammo reward
+	pub fn refill(&mut self, ammunition: &Ammunition) {
+		self.mag_remaining = ammunition.magasin_size;
+		self.remaining_ammunition = ammunition.reserve_limit();
+	}
+
+	// add to the reserve up to the limit , return the ammunition really added
+	pub fn add_ammunition(&mut self, ammunition: &Ammunition, amount: i32) -> i32 {
+		let added = amount.max(0).min((ammunition.reserve_limit() - self.remaining_ammunition).max(0));
+		self.remaining_ammunition += added;
+		added
+	}
+
+	pub fn clamp_to_limit(&mut self, ammunition: &Ammunition) {
+		self.mag_remaining = self.mag_remaining.min(ammunition.magasin_size);
+		self.remaining_ammunition = self.remaining_ammunition.min(ammunition.reserve_limit());
+	}
+
+	pub fn is_full(&self, ammunition: &Ammunition) -> bool {
+		self.remaining_ammunition >= ammunition.reserve_limit()
+	}
+}
+
+// Ammunition picked up by a weapon , or by every weapon of a player when inserted on
+// the player. Inserted by the rollback systems of the wall buys and the power ups so
+// the pickup is part of the rollback state , it's removed once applied
+#[derive(Default, Component, Reflect)]
+pub struct AmmunitionPickup {
+	// fill the magasin and the reserve
+	pub refill: bool,
+	// ammunition added to the reserve
+	pub amount: i32,
+}
+
 
 #[derive(Default, PartialEq)]
 pub enum WeaponCurrentAction {
@@ -84,33 +226,87 @@ pub struct WeaponState {
 	pub state: WeaponCurrentAction
 }
 
-#[derive(Default, Component)]
+// Weapon in the hands of the player , part of the rollback state as the weapons are
+// switched and replaced by the rollback systems
+#[derive(Default, Component, Reflect)]
 pub struct ActiveWeapon {}
 
+// Slot of a weapon carried by a player , from 0 , the order of the weapon selection
+#[derive(Default, Component, Reflect)]
+pub struct WeaponSlot(pub usize);
+
+// Number of weapons a player can carry
+#[derive(Default, Component, Reflect)]
+pub struct WeaponSlots {
+    pub limit: usize,
+}
+
+// Weapon given to a player , inserted on the player by the rollback systems of the wall-buys
+// and the mystery box so it's part of the rollback state , removed once given. At the carry
+// limit the active weapon is replaced , a weapon already carried get its ammunition back
+#[derive(Default, Component, Reflect)]
+pub struct GiveWeapon {
+    pub name: String,
+}
+
+// Raise the carry limit of the player to the `weapon_carry_limit_perk` of the level , inserted
+// like `GiveWeapon` and removed once applied
+#[derive(Default, Component, Reflect)]
+pub struct WeaponCarryPerk {}
+
+#[derive(Default, Component, Reflect)]
+pub struct Projectile {
+    pub damage: f32,
+    // zombies the projectile can still go through
+    pub penetration: u32,
+    // part of the damage lost each time the projectile go through a zombie
+    pub damage_loss: f32,
+}
+
+impl Projectile {
+    pub fn new(weapon: &Weapon) -> Self {
+        Projectile {
+            damage: weapon.damage,
+            penetration: weapon.penetration.max(1),
+            damage_loss: weapon.penetration_damage_loss,
+        }
+    }
+}
+
+// Rollback id of the zombies already hit by a projectile , so a zombie isn't hit twice
 #[derive(Default, Component, Reflect)]
-pub struct Projectile {}
+pub struct ProjectileHits {
+    pub hits: Vec<u32>,
+}
 
 
 #[derive(Bundle)]
 pub struct WeaponBundle {
 	pub weapon: Weapon,
 	pub ammunition_state: AmmunitionState,
-	pub weapon_state: WeaponState
+	pub weapon_state: WeaponState,
+	pub recoil: WeaponRecoil,
+	pub checksum: Checksum,
 }
 
 
 impl WeaponBundle {
-	pub fn new(weapon: Weapon) -> Self {
+	// the starting ammunition outside the magasin is multiply by `ammunition_multiplier`
+	pub fn new(weapon: Weapon, ammunition_multiplier: f32) -> Self {
+		let remaining_ammunition = (weapon.ammunition.magasin_nbr_starting - 1) * weapon.ammunition.magasin_size;
+		let remaining_ammunition = (remaining_ammunition as f32 * ammunition_multiplier).round() as i32;
 		WeaponBundle { 
 			ammunition_state: AmmunitionState {
 				mag_remaining: weapon.ammunition.magasin_size,
-				remaining_ammunition: (weapon.ammunition.magasin_nbr_starting - 1) * weapon.ammunition.magasin_size
+				remaining_ammunition: remaining_ammunition.min(weapon.ammunition.reserve_limit())
 			},
 			weapon,
 			weapon_state: WeaponState{
 				fired_at: 0.,
 				state: WeaponCurrentAction::Firing
-			}
+			},
+			recoil: WeaponRecoil::default(),
+			checksum: Checksum::default(),
 		}
 	}
 }
@@ -157,16 +353,21 @@ pub fn handle_weapon_input(
     mut commands: Commands,
     time: Res<Time>,
     
-    query_unequiped_weapon: Query<(Entity, &Weapon), Without<ActiveWeapon>>,
-	mut query_player_weapon: Query<(Entity, &mut AmmunitionState, &mut WeaponState, &Weapon, &ActiveWeapon), With<WeaponState>>,
+    mut query_unequiped_weapon: Query<(Entity, &Weapon, &mut WeaponRecoil), Without<ActiveWeapon>>,
+    query_weapon_slot: Query<&WeaponSlot>,
+	mut query_player_weapon: Query<(Entity, &mut AmmunitionState, &mut WeaponState, &Weapon, &ActiveWeapon, &mut WeaponRecoil), With<WeaponState>>,
 	
-    mut q_player: Query<(&GlobalTransform, &PlayerCurrentInput, &LookingAt, &mut CharacterMovementState, &mut AnimationTimer, &Children, &Player), (Without<Death>)>,
+    mut q_player: Query<(&GlobalTransform, &PlayerCurrentInput, &LookingAt, &mut CharacterMovementState, &mut AnimationTimer, &Children, &Player, &Velocity), (Without<Death>)>,
 
     inputs: Res<Vec<(BoxInput, InputStatus)>>,
+    frame_count: Res<FrameCount>,
+    game_speed: Res<GameSpeed>,
 
     mut rip: ResMut<RollbackIdProvider>,
+
+    mut collider_query: HitscanColliderQuery,
 ) {
-    for (player_global_transform, current_input, looking_at, mut movement_state, mut timer, childrens, player) in q_player.iter_mut() {
+    for (player_global_transform, current_input, looking_at, mut movement_state, mut timer, childrens, player, velocity) in q_player.iter_mut() {
 
         if inputs.len() <= player.handle {
             continue;
@@ -178,11 +379,31 @@ pub fn handle_weapon_input(
             InputStatus::Disconnected => BoxInput::default(), // disconnected players do nothing
         };
         
-        if box_input.inp & INPUT_WEAPON_CHANGED == INPUT_WEAPON_CHANGED {
-            for children in childrens.iter() {
-                if let Ok((weapon_entity,_,_, _, _)) = query_player_weapon.get(*children) {
-                    commands.entity(weapon_entity).remove::<ActiveWeapon>();
-                } else if let Ok((weapon_entity, weapon)) = query_unequiped_weapon.get(*children) {
+        let next = box_input.inp & INPUT_WEAPON_CHANGED == INPUT_WEAPON_CHANGED;
+        let previous = box_input.inp & INPUT_WEAPON_PREVIOUS == INPUT_WEAPON_PREVIOUS;
+        let selected_slot = ((box_input.inp & INPUT_WEAPON_SLOT_MASK) >> INPUT_WEAPON_SLOT_SHIFT) as usize;
+        if next || previous || selected_slot > 0 {
+            // the weapons sorted by slot so the cycle is the same on every peer
+            let mut carried: Vec<(usize, Entity)> = childrens.iter()
+                .filter_map(|x| query_weapon_slot.get(*x).ok().map(|slot| (slot.0, *x)))
+                .collect();
+            carried.sort_by_key(|x| x.0);
+
+            let active = carried.iter().position(|x| query_player_weapon.get(x.1).is_ok());
+            let target = if selected_slot > 0 {
+                carried.iter().position(|x| x.0 == selected_slot - 1)
+            } else if carried.is_empty() {
+                None
+            } else {
+                let active = active.unwrap_or(0);
+                Some(if next { (active + 1) % carried.len() } else { (active + carried.len() - 1) % carried.len() })
+            };
+
+            if let Some(target) = target.filter(|x| Some(*x) != active) {
+                if let Some(active) = active {
+                    commands.entity(carried[active].1).remove::<ActiveWeapon>();
+                }
+                if let Ok((weapon_entity, weapon, _)) = query_unequiped_weapon.get(carried[target].1) {
                     commands.entity(weapon_entity).insert(ActiveWeapon{});
 
                     movement_state.sub_state = weapon.name.clone();
@@ -192,7 +413,14 @@ pub fn handle_weapon_input(
         }
 
         for children in childrens.iter() {
-            if let Ok((_,mut ammunition_state, mut weapon_state, weapon, _)) = query_player_weapon.get_mut(*children) {
+            // the weapons carried recover from their recoil too , it's not kept until they are active again
+            if let Ok((_, weapon, mut recoil)) = query_unequiped_weapon.get_mut(*children) {
+                recoil.current = (recoil.current - weapon.recoil_recovery * game_speed.0).max(0.);
+            }
+
+            if let Ok((_,mut ammunition_state, mut weapon_state, weapon, _, mut recoil)) = query_player_weapon.get_mut(*children) {
+                recoil.current = (recoil.current - weapon.recoil_recovery * game_speed.0).max(0.);
+
                 if weapon_state.state == WeaponCurrentAction::Reloading {
                     let current_time = time.time_since_startup().as_secs_f32();
                     if current_time < weapon_state.fired_at + weapon.reloading_time {
@@ -244,23 +472,11 @@ pub fn handle_weapon_input(
 
                     let parent_location = player_global_transform.translation;
 
-                    let mut diff = (if !looking_at.1 { 
+                    let diff = (if !looking_at.1 { 
                         let mouse_location = looking_at.0;
                         Vec2::new(mouse_location.x - parent_location.x, mouse_location.y - parent_location.y).normalize()
                     } else { looking_at.0 }).normalize();
 
-                    if weapon.offset > 0 {
-                        let bottom = weapon.offset * -1;
-                        let top = weapon.offset * 1;
-
-                        let mut ndg = rand::thread_rng();
-                        let mut range: Vec<f32> = (bottom..top).map(|x| x as f32).collect();
-                        range.shuffle(&mut ndg);
-                        
-                        diff.x += range[0] / 100.;
-                        diff.y += range[1] / 100.;
-                    }
-
                     let (starting_point, offset_each) = if weapon.firing_ammunition == 1 {
                         (parent_location, Vec2::new(0.,0.))
                     } else {
@@ -274,24 +490,22 @@ pub fn handle_weapon_input(
                         )
                     };
 
+                    // the random of the spread come from the frame and the player so every peer fire the same way
+                    let seed = frame_count.frame.wrapping_mul(7919) ^ ((player.handle as u32) << 24);
+                    let moving = velocity.v != Vec2::ZERO;
+
                     for i in (0..weapon.firing_ammunition) {
                         ammunition_state.mag_remaining -= 1;
-                        
-
-                        if weapon.spreading_ammunition > 1 {
-                            for x in 0..weapon.spreading_ammunition/2 {
-                                let scale: f32 = if x % 2 == 0 { 1. } else { -1. };
-                                let angle: f32 = ((x as f32) / 20.) * scale;
 
-                                let new_x = diff.x * angle.cos() - diff.y * angle.sin();
-                                let new_y = diff.x * angle.sin() + diff.y * angle.cos();
-
-                                spawn_bullet(&mut commands, &mut rip, &weapon, &time, &starting_point, &offset_each, &Vec2::new(new_x, new_y), i);
+                        let accuracy = weapon.accuracy(moving, &recoil);
+                        for direction in weapon.pellet_directions(diff, accuracy, deterministic_random(seed.wrapping_add(i))) {
+                            if weapon.hitscan {
+                                fire_hitscan(&mut commands, &mut rip, &weapon, &time, &starting_point, &offset_each, &direction, i, &mut collider_query);
+                            } else {
+                                spawn_bullet(&mut commands, &mut rip, &weapon, &time, &starting_point, &offset_each, &direction, i);
                             }
-                        } else {
-                            spawn_bullet(&mut commands, &mut rip, &weapon, &time, &starting_point, &offset_each, &diff, i);
                         }
-
+                        recoil.current = (recoil.current + weapon.recoil).min(weapon.max_recoil);
                     }
 
                 }
@@ -312,14 +526,20 @@ pub fn spawn_bullet(
 ) {
     commands
         .spawn()
-        .insert(Projectile {})
+        .insert(Projectile::new(weapon))
+        .insert(ProjectileHits::default())
+        .insert(Checksum::default())
         .insert_bundle(SpriteBundle {
             transform: Transform {
                 translation: *starting_point + (offset_each.extend(0.) * index as f32),
                 ..Transform::default()
             },
             sprite: Sprite {
-                color: Color::BISQUE,
+                color: Color::rgb(
+                    weapon.ammunition.sprite_config.color.x,
+                    weapon.ammunition.sprite_config.color.y,
+                    weapon.ammunition.sprite_config.color.z,
+                ),
                 custom_size: Some(weapon.ammunition.sprite_config.size),
                 ..Sprite::default()
             },
@@ -336,3 +556,97 @@ pub fn spawn_bullet(
         .insert(Rollback::new(rip.next_id()));
 }
 
+pub fn system_give_weapon(
+    mut commands: Commands,
+    mut rip: ResMut<RollbackIdProvider>,
+    weapons: Res<WeaponAssetState>,
+    config: Res<ZombieGameConfig>,
+
+    mut q_player: Query<(Entity, &GiveWeapon, &Children, &WeaponSlots, &mut CharacterMovementState, &mut AnimationTimer)>,
+    mut q_weapon: Query<(&WeaponSlot, &Weapon, &mut AmmunitionState, Option<&ActiveWeapon>)>,
+) {
+    for (player, give_weapon, childrens, slots, mut movement_state, mut timer) in q_player.iter_mut() {
+        commands.entity(player).remove::<GiveWeapon>();
+        let weapon = match weapons.weapons.iter().find(|w| w.name.eq(give_weapon.name.as_str())) {
+            Some(weapon) => weapon.clone(),
+            None => {
+                println!("Unknown weapon {}", give_weapon.name);
+                continue;
+            }
+        };
+
+        let mut carried: Vec<(usize, Entity, bool)> = vec![];
+        let mut owned = false;
+        for children in childrens.iter() {
+            if let Ok((slot, carried_weapon, mut ammo_state, active)) = q_weapon.get_mut(*children) {
+                // the weapon or its upgraded variant is already carried
+                if carried_weapon.name == weapon.name || carried_weapon.upgrade_of.as_ref() == Some(&weapon.name) {
+                    ammo_state.refill(&carried_weapon.ammunition);
+                    owned = true;
+                }
+                carried.push((slot.0, *children, active.is_some()));
+            }
+        }
+        if owned {
+            continue;
+        }
+
+        let active = carried.iter().find(|x| x.2).cloned();
+        let slot = match active {
+            Some((slot, entity, _)) if carried.len() >= slots.limit => {
+                commands.entity(entity).despawn_recursive();
+                slot
+            },
+            None if carried.len() >= slots.limit => continue,
+            _ => {
+                if let Some((_, entity, _)) = active {
+                    commands.entity(entity).remove::<ActiveWeapon>();
+                }
+                (0..).find(|s| !carried.iter().any(|x| x.0 == *s)).unwrap()
+            },
+        };
+
+        movement_state.sub_state = weapon.name.clone();
+        timer.offset = weapon.sprite_sheet_offset;
+
+        let weapon = commands.spawn()
+            .insert_bundle(WeaponBundle::new(weapon, config.difficulty.ammunition_multiplier))
+            .insert(WeaponSlot(slot))
+            .insert(ActiveWeapon{})
+            .insert(Rollback::new(rip.next_id())).id();
+        commands.entity(player).add_child(weapon);
+    }
+}
+
+pub fn system_weapon_carry_perk(
+    mut commands: Commands,
+    config: Res<ZombieGameConfig>,
+    mut q_slots: Query<(Entity, &mut WeaponSlots), With<WeaponCarryPerk>>,
+) {
+    for (player, mut slots) in q_slots.iter_mut() {
+        commands.entity(player).remove::<WeaponCarryPerk>();
+        slots.limit = slots.limit.max(config.starting_weapons.weapon_carry_limit_perk);
+    }
+}
+
+pub fn system_ammunition_pickup(
+	mut commands: Commands,
+	query_pickup: Query<(Entity, &AmmunitionPickup, Option<&Children>)>,
+	mut query_weapon: Query<(&mut AmmunitionState, &Weapon)>,
+) {
+	for (entity, pickup, childrens) in query_pickup.iter() {
+		let weapons: Vec<Entity> = match childrens {
+			Some(childrens) if query_weapon.get(entity).is_err() => childrens.iter().cloned().collect(),
+			_ => vec![entity],
+		};
+		for weapon_entity in weapons {
+			if let Ok((mut ammunition_state, weapon)) = query_weapon.get_mut(weapon_entity) {
+				if pickup.refill {
+					ammunition_state.refill(&weapon.ammunition);
+				}
+				ammunition_state.add_ammunition(&weapon.ammunition, pickup.amount);
+			}
+		}
+		commands.entity(entity).remove::<AmmunitionPickup>();
+	}
+}
@@ -1,19 +1,107 @@
-use std::time::Duration;
-
 use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::shared::{map::{MapElementPosition, ZombieSpawner, PlayerSpawn}, utils::deterministic_random};
+
+// distance to the closest player where the weight of a spawner is divided by two
+const SPAWNER_DISTANCE_FALLOFF: f32 = 400.;
+
+// How the spawners are selected each time the spawn timer finish
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Reflect, Default, Deserialize)]
+pub enum SpawnerSelection {
+    // every spawner spawn a zombie
+    #[default]
+    All,
+    // the spawners take turn
+    RoundRobin,
+    // random spawners , the weight of the spawners is reduced with the distance to the closest player
+    WeightedByDistance,
+}
 
-pub struct ZombieSpawnerConfig {
-    pub timer: Timer,
-    pub nums_ndg: Vec<f32>,
+pub struct SpawnerCandidate {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub weight: f32,
 }
 
-impl FromWorld for ZombieSpawnerConfig {
-    fn from_world(world: &mut World) -> Self {
-        ZombieSpawnerConfig{
-            timer: Timer::new(Duration::from_secs(5), true),
-            nums_ndg: (-50..50).map(|x| x as f32).collect()
+// Zones of the player spawns closest to each player
+pub fn active_zones(spawns: &Vec<(Vec2, PlayerSpawn)>, players: &Vec<Vec2>) -> Vec<String> {
+    let mut zones = vec![];
+    for player in players.iter() {
+        let closest = spawns.iter()
+            .min_by(|a, b| a.0.distance(*player).partial_cmp(&b.0.distance(*player)).unwrap());
+        if let Some((_, PlayerSpawn { zone: Some(zone), .. })) = closest {
+            if !zones.contains(zone) {
+                zones.push(zone.clone());
+            }
         }
     }
+    zones
 }
 
+// Spawners out of cooldown and , when `active_zones` is set , without zone or in one of them.
+// They are sorted by position so every peer select the same ones
+pub fn spawner_candidates<'a>(
+    spawners: impl Iterator<Item = (Entity, &'a MapElementPosition, &'a ZombieSpawner)>,
+    active_zones: Option<&Vec<String>>,
+) -> Vec<SpawnerCandidate> {
+    let mut candidates: Vec<SpawnerCandidate> = spawners
+        .filter(|(_, _, s)| s.cooldown_remaining == 0)
+        .filter(|(_, _, s)| match (active_zones, s.zone.as_ref()) {
+            (Some(zones), Some(zone)) => zones.contains(zone),
+            _ => true,
+        })
+        .map(|(entity, p, s)| SpawnerCandidate { entity, position: p.position, weight: s.weight.max(0.) })
+        .collect();
+    candidates.sort_by(|a, b| a.position.x.partial_cmp(&b.position.x).unwrap().then(a.position.y.partial_cmp(&b.position.y).unwrap()));
+    candidates
+}
+
+// Index of the candidates spawning a zombie , `count` is the number of zombies
+// spawned by the round robin and weighted selection
+pub fn select_spawners(
+    selection: &SpawnerSelection,
+    candidates: &Vec<SpawnerCandidate>,
+    players: &Vec<Vec2>,
+    count: usize,
+    round_robin_index: &mut usize,
+    seed: u32,
+) -> Vec<usize> {
+    if candidates.len() == 0 {
+        return vec![];
+    }
+    match selection {
+        SpawnerSelection::All => (0..candidates.len()).collect(),
+        SpawnerSelection::RoundRobin => {
+            (0..count.min(candidates.len())).map(|_| {
+                let index = *round_robin_index % candidates.len();
+                *round_robin_index = round_robin_index.wrapping_add(1);
+                index
+            }).collect()
+        },
+        SpawnerSelection::WeightedByDistance => {
+            let mut weights: Vec<f32> = candidates.iter().map(|c| {
+                let distance = players.iter().map(|p| p.distance(c.position)).fold(f32::MAX, f32::min);
+                let distance = if players.len() == 0 { 0. } else { distance };
+                c.weight / (1. + distance / SPAWNER_DISTANCE_FALLOFF)
+            }).collect();
 
+            let mut selected = vec![];
+            for i in 0..count.min(candidates.len()) {
+                let total: f32 = weights.iter().sum();
+                if total <= 0. {
+                    break;
+                }
+                let mut target = deterministic_random(seed.wrapping_add(i as u32)) * total;
+                let index = weights.iter().position(|w| {
+                    target -= w;
+                    target < 0.
+                }).unwrap_or_else(|| weights.iter().rposition(|w| *w > 0.).unwrap());
+                // a spawner is only selected once each time
+                weights[index] = 0.;
+                selected.push(index);
+            }
+            selected
+        },
+    }
+}
@@ -8,10 +8,9 @@ use crate::shared::{
     health::Health, animation::AnimationTimer, character::{LookingAt, CharacterMovementState, Death}, utils::{vec2_perpendicular_counter_clockwise, Checksum}
 };
 
-use rand::seq::SliceRandom;
 use pathfinding::prelude::astar;
+use serde::Deserialize;
 
-use super::spawner::ZombieSpawnerConfig;
 
 pub const ZOMBIE_SIZE: Vec2 = const_vec2!([25. , 25. ]);
 
@@ -26,29 +25,43 @@ pub struct BotDestination {
     // Precalculate path to reach the target
     pub path: Vec<(i32, i32)>,
     // entity trying to reach
-    pub entity: Entity
+    pub entity: Entity,
+    // steps of the path taken each frame
+    pub speed: f32,
+    // part of a step not taken yet when the speed is not a whole number
+    pub step_progress: f32,
 }
 
 impl Default for BotDestination {
    fn default() -> Self {
-       BotDestination { destination: Vec2::default(), path: vec![], entity: Entity::from_raw(0), requested_movement: None, }
+       BotDestination { destination: Vec2::default(), path: vec![], entity: Entity::from_raw(0), requested_movement: None, speed: 1., step_progress: 0. }
    }
 }
 
 impl BotDestination {
 
-    pub fn move_bot<T: Component, R: Component>(&mut self, pos: &mut Transform, collider_query: &Query<
+    pub fn move_bot<T: Component, R: Component>(&mut self, pos: &mut Transform, size: Vec2, collider_query: &Query<
         (Entity, &Transform, &MovementCollider),
         (Without<T>, Without<R>)
     >,) -> bool {
-        if let Some(el) = self.path.pop() {
-            if !is_colliding(Vec3::new(el.0 as f32, el.1 as f32, 10.), ZOMBIE_SIZE, "zombie", &collider_query) {
-                self.requested_movement = Some(Vec2::new(el.0 as f32, el.1 as f32));
-            }
-            return true;
-        } else {
+        if self.path.len() == 0 {
             return false;
         }
+        self.step_progress += self.speed;
+        while self.step_progress >= 1. {
+            self.step_progress -= 1.;
+            let el = match self.path.pop() {
+                Some(el) => el,
+                None => break,
+            };
+            // the bot stop before the first blocked node , it can't go through it to the next ones
+            if is_colliding(Vec3::new(el.0 as f32, el.1 as f32, 10.), size, "zombie", &collider_query) {
+                self.step_progress = 0.;
+                break;
+            }
+            self.requested_movement = Some(Vec2::new(el.0 as f32, el.1 as f32));
+        }
+        return true;
     }
 
     // set_destination get a element position and entity id , keep it and recalculate the path required to get there
@@ -115,9 +128,44 @@ pub enum ZombieState {
 }
 
 
+// The kind of enemy , the hellhounds go straight for the players
+// without entering by the windows and the boss is bigger
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Reflect, Default, Deserialize)]
+pub enum ZombieKind {
+    #[default]
+    Zombie,
+    Hellhound,
+    Boss,
+}
+
+impl ZombieKind {
+    pub fn enter_by_windows(&self) -> bool {
+        *self != ZombieKind::Hellhound
+    }
+
+    pub fn scale(&self) -> f32 {
+        match self {
+            ZombieKind::Zombie => 1.,
+            ZombieKind::Hellhound => 0.8,
+            ZombieKind::Boss => 1.6,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            ZombieKind::Zombie => Color::WHITE,
+            ZombieKind::Hellhound => Color::rgb(1., 0.5, 0.2),
+            ZombieKind::Boss => Color::rgb(0.7, 0.2, 0.2),
+        }
+    }
+}
+
 #[derive(Component, Reflect, Default)]
 pub struct Zombie {
     pub state: ZombieState,
+    pub kind: ZombieKind,
+    // health removed from a window panel or a player by each attack
+    pub damage: f32,
 }
 
 
@@ -130,6 +178,7 @@ pub struct ZombieBundle {
     projectile_collider: ProjectileCollider,
     info: MapElementPosition,
     zombie: Zombie,
+    health: Health,
     weapon_state: WeaponState,
     animation_timer: AnimationTimer,
     looking_at: LookingAt,
@@ -138,7 +187,9 @@ pub struct ZombieBundle {
 }
 
 impl ZombieBundle {
-    pub fn new(info: MapElementPosition, dest: BotDestination) -> ZombieBundle {
+    pub fn new(mut info: MapElementPosition, dest: BotDestination, kind: ZombieKind, health: f32, damage: f32) -> ZombieBundle {
+        // the colliders follow the scale of the sprite
+        info.size = info.size * kind.scale();
         ZombieBundle {
             sprite_bundle: SpriteSheetBundle {
                transform: Transform {
@@ -146,17 +197,24 @@ impl ZombieBundle {
                     scale: Vec3::new(0., 0., 0.),
                     ..Transform::default()
                 },
+                sprite: TextureAtlasSprite {
+                    color: kind.color(),
+                    ..default()
+                },
                 ..default()
             },
             collider: MovementCollider {
-                size: ZOMBIE_SIZE,
+                size: ZOMBIE_SIZE * kind.scale(),
                 ..default()
             },
             checksum: Checksum::default(),
             projectile_collider: ProjectileCollider {},
             zombie: Zombie {
                 state: ZombieState::AwakingFromTheDead,
+                kind,
+                damage,
             },
+            health: Health { current_health: health, tmp_health: health, max_health: health },
             chracter_movement_state: CharacterMovementState { state: "rising".to_string(), sub_state: "".to_string() },
             animation_timer: AnimationTimer {
                 timer: Timer::from_seconds(0.1, true),
@@ -190,7 +248,6 @@ pub fn system_move_zombie(
 pub fn system_zombie_handle(
     // mut commands: Commands,
     query_player: Query<(Entity, &Transform), (With<Player>, Without<Zombie>, Without<Death>)>,
-    mut config: ResMut<ZombieSpawnerConfig>,
     mut query_zombies: Query<(&mut Transform, &mut BotDestination, &mut Zombie, &mut WeaponState, &mut LookingAt, &mut CharacterMovementState), With<Zombie>>,
     //mut query_windows: Query<(&mut Window, Entity, &Children)>,
     //mut query_panel: Query<(&WindowPanel, &mut Sprite, &mut Health)>,
@@ -206,23 +263,21 @@ pub fn system_zombie_handle(
     for (mut pos, mut dest, mut zombie, mut weapon_state, mut looking_at, mut movement_state) in query_zombies.iter_mut() {
         match zombie.state {
             ZombieState::AwakingFromTheDead => {
-                if pos.scale.x < 1.0 {
-                    //let mut rng = rand::thread_rng();
-                    //config.nums_ndg.shuffle(&mut rng);
-                    pos.scale += Vec3::new(0.01, 0.01, 0.01);
+                if pos.scale.x < zombie.kind.scale() {
+                    pos.scale += Vec3::new(0.01, 0.01, 0.01) * zombie.kind.scale();
                 } else {
                     pos.rotation = Quat::from_rotation_z(0.);
-                    zombie.state = ZombieState::FindingEnterace;
+                    zombie.state = if zombie.kind.enter_by_windows() { ZombieState::FindingEnterace } else { ZombieState::FollowingPlayer };
                     movement_state.state = "walking".to_string();
                 }
             }
             ZombieState::FindingEnterace => {
-                if !dest.move_bot(&mut pos, &collider_query) {
+                if !dest.move_bot(&mut pos, ZOMBIE_SIZE * zombie.kind.scale(), &collider_query) {
                     if let Ok((entity, mut health)) = query_ennemy.get_mut(dest.entity) {
                         if health.current_health > 0. {
                             let current_time = time.time_since_startup().as_secs_f32();
                             if !(current_time < weapon_state.fired_at + 1.) {
-                                health.tmp_health -= 1.;
+                                health.tmp_health -= zombie.damage;
                                 weapon_state.fired_at = current_time;
                             }
                         } else {
@@ -244,12 +299,12 @@ pub fn system_zombie_handle(
                 }
             }
             ZombieState::CrossingEntrance => {
-                if !dest.move_bot(&mut pos, &collider_query) {
+                if !dest.move_bot(&mut pos, ZOMBIE_SIZE * zombie.kind.scale(), &collider_query) {
                     zombie.state = ZombieState::FollowingPlayer;
                 }
             },
             ZombieState::FollowingPlayer => {
-                if !dest.move_bot(&mut pos, &collider_query) {
+                if !dest.move_bot(&mut pos, ZOMBIE_SIZE * zombie.kind.scale(), &collider_query) {
                     
                     // Query the players to find the closest
                     let mut distance = 50000.;
@@ -270,7 +325,7 @@ pub fn system_zombie_handle(
                         if let Ok((_, mut health)) = query_ennemy.get_mut(dest.entity) {
                             let current_time = time.time_since_startup().as_secs_f32();
                             if !(current_time < weapon_state.fired_at + 1.) {
-                                health.tmp_health -= 1.;
+                                health.tmp_health -= zombie.damage;
                                 weapon_state.fired_at = current_time;
                             }
                         }